@@ -1,5 +1,5 @@
 use crate::ray::Ray;
-use crate::util::rand_in_unit_circle;
+use crate::util::concentric_disk;
 use crate::vec3::Vec3;
 
 /// Camera Construction parameters
@@ -65,10 +65,11 @@ impl Camera {
     }
 
     /// Return a ray corresponsing to a particular point along the camera's
-    /// conceptual "window" into the world.
-    pub fn get_ray(&self, du: f32, dv: f32) -> Ray {
-        let rd = self.lens_radius * rand_in_unit_circle();
-        let offset = self.u * rd.x + self.v * rd.y;
+    /// conceptual "window" into the world, passing through the lens at a
+    /// point picked by `lens` (a point in the unit square).
+    pub fn get_ray(&self, lens: (f32, f32), du: f32, dv: f32) -> Ray {
+        let (lx, ly) = concentric_disk(lens);
+        let offset = self.lens_radius * (self.u * lx + self.v * ly);
 
         let origin = self.origin + offset;
         Ray::new(
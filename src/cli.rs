@@ -0,0 +1,265 @@
+//! Command-line interface
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use structopt::StructOpt;
+
+use crate::render::{Aov, DisplayOpts, RenderOpts, TileOrder, Tonemap};
+use crate::sampler::SamplerKind;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "vrai-tracer",
+    about = "A toy ray tracer, following along with RTIOW"
+)]
+pub struct Cli {
+    #[structopt(subcommand)]
+    cmd: Option<Command>,
+}
+
+impl Cli {
+    /// Returns the selected subcommand, defaulting to `preview` with default
+    /// options if none was specified.
+    pub fn command(self) -> Command {
+        self.cmd.unwrap_or_else(|| Command::Preview {
+            args: RenderArgs::from_iter(&["preview"]),
+        })
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub enum Command {
+    /// Open a window with a live preview of the scene (default)
+    Preview {
+        #[structopt(flatten)]
+        args: RenderArgs,
+    },
+    /// Render a single frame without opening a window, and save it to disk
+    Render {
+        #[structopt(flatten)]
+        args: RenderArgs,
+        /// Output image path. The format is picked based on the extension:
+        /// `.png` (tone mapped), or `.hdr`, `.pfm`, and `.exr` (linear)
+        #[structopt(short, long, parse(from_os_str))]
+        output: PathBuf,
+        /// Also write out an AOV (`depth`, `normal`, `position`, `albedo`,
+        /// `material-id`, or `object-id`). May be given multiple times.
+        #[structopt(long = "aov", number_of_values = 1)]
+        aovs: Vec<Aov>,
+        /// Also write out a heatmap of the number of samples taken at each
+        /// pixel (in any of the output formats)
+        #[structopt(long, parse(from_os_str))]
+        heatmap: Option<PathBuf>,
+    },
+    /// Render a few frames without opening a window, and report timings
+    Bench {
+        #[structopt(flatten)]
+        args: RenderArgs,
+        /// Number of frames to render
+        #[structopt(long, default_value = "5", parse(try_from_str = parse_nonzero))]
+        frames: usize,
+    },
+}
+
+impl Command {
+    /// Returns the options shared by all subcommands
+    pub fn args(&self) -> &RenderArgs {
+        match self {
+            Command::Preview { args } => args,
+            Command::Render { args, .. } => args,
+            Command::Bench { args, .. } => args,
+        }
+    }
+}
+
+// Options shared by all subcommands.
+// (not a doc comment, since structopt would use it as the `about` text of every
+// subcommand that flattens it)
+#[derive(Debug, StructOpt)]
+pub struct RenderArgs {
+    /// Samples per-pixel (when sampling adaptively, this is the most samples a
+    /// pixel may take in a single pass)
+    // speedy, but grainy
+    #[structopt(short, long, default_value = "4", parse(try_from_str = parse_nonzero))]
+    pub samples: usize,
+    /// Enable adaptive sampling: pixels stop taking samples once their
+    /// relative noise level drops below this threshold (e.g: `0.02`)
+    #[structopt(long)]
+    pub noise_threshold: Option<f32>,
+    /// Samples each pixel takes before it may stop early (when sampling
+    /// adaptively) [default: same as `--samples`]
+    #[structopt(long, parse(try_from_str = parse_nonzero))]
+    pub min_samples: Option<usize>,
+    /// Maximum samples per-pixel, across all passes (when sampling adaptively)
+    #[structopt(long, default_value = "1024", parse(try_from_str = parse_nonzero))]
+    pub max_samples: usize,
+    /// Output resolution, of the form `WxH` (e.g: `640x480`)
+    #[structopt(short, long, default_value = "256x128")]
+    pub resolution: Resolution,
+    /// Scene to render (`random`, `chapter`, or a path to a `.toml` scene file)
+    #[structopt(long, default_value = "random")]
+    pub scene: SceneKind,
+    /// Number of render threads [default: number of CPUs]
+    #[structopt(short = "j", long, parse(try_from_str = parse_nonzero))]
+    pub threads: Option<usize>,
+    /// Seed for the renderer's random numbers [default: 0]. Also seeds the
+    /// `random` scene, which is otherwise different on every run.
+    #[structopt(long)]
+    pub seed: Option<u64>,
+    /// Sample generator (`independent`, `stratified`, `halton`, or `sobol`)
+    #[structopt(long, default_value = "sobol")]
+    pub sampler: SamplerKind,
+    /// Maximum number of times a ray may bounce
+    #[structopt(long, default_value = "50")]
+    pub max_depth: usize,
+    /// Number of bounces after which paths are randomly terminated (via Russian
+    /// roulette)
+    #[structopt(long, default_value = "5")]
+    pub rr_depth: usize,
+    /// Width and height of the tiles the image is split into while rendering
+    #[structopt(long, default_value = "16", parse(try_from_str = parse_nonzero))]
+    pub tile_size: usize,
+    /// Order in which tiles are rendered (`scanline`, `random`, `spiral`, or
+    /// `hilbert`)
+    #[structopt(long, default_value = "spiral")]
+    pub tile_order: TileOrder,
+    /// Tone mapping operator (`linear`, `reinhard`, `reinhard-extended`,
+    /// `hable`, or `aces`)
+    #[structopt(long, default_value = "linear")]
+    pub tonemap: Tonemap,
+    /// Exposure adjustment, in stops
+    #[structopt(long, default_value = "0", allow_hyphen_values = true)]
+    pub exposure: f32,
+    /// Luminance mapped to white by the `reinhard-extended` operator
+    #[structopt(long, default_value = "4")]
+    pub white_point: f32,
+    /// Denoise the image (guided by the normal, position, and albedo AOVs)
+    #[structopt(long)]
+    pub denoise: bool,
+}
+
+impl RenderArgs {
+    /// Returns a [RenderOpts] corresponding to these arguments
+    pub fn render_opts(&self) -> RenderOpts {
+        RenderOpts {
+            width: self.resolution.width,
+            height: self.resolution.height,
+            samples: self.samples,
+            threads: self.threads.unwrap_or_else(num_cpus::get),
+            max_depth: self.max_depth,
+            seed: self.seed.unwrap_or(0),
+            rr_depth: self.rr_depth,
+            tile_size: self.tile_size,
+            tile_order: self.tile_order,
+            noise_threshold: self.noise_threshold,
+            min_samples: self.min_samples.unwrap_or(self.samples),
+            max_samples: self.max_samples,
+            sampler: self.sampler,
+        }
+    }
+
+    /// Returns a [DisplayOpts] corresponding to these arguments
+    pub fn display_opts(&self) -> DisplayOpts {
+        DisplayOpts {
+            tonemap: self.tonemap,
+            exposure: self.exposure,
+            white_point: self.white_point,
+        }
+    }
+}
+
+/// Image resolution, parsed from a string of the form `WxH`
+#[derive(Debug, Copy, Clone)]
+pub struct Resolution {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl FromStr for Resolution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Resolution, String> {
+        let mut dims = s.splitn(2, 'x');
+        match (dims.next(), dims.next()) {
+            (Some(w), Some(h)) => Ok(Resolution {
+                width: parse_nonzero(w)
+                    .map_err(|e| format!("bad width in resolution '{}': {}", s, e))?,
+                height: parse_nonzero(h)
+                    .map_err(|e| format!("bad height in resolution '{}': {}", s, e))?,
+            }),
+            _ => Err(format!(
+                "expected resolution of the form WxH (e.g: 640x480), got '{}'",
+                s
+            )),
+        }
+    }
+}
+
+/// Built-in scenes, or a scene description file
+#[derive(Debug, Clone)]
+pub enum SceneKind {
+    Random,
+    Chapter,
+    File(PathBuf),
+}
+
+impl FromStr for SceneKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<SceneKind, String> {
+        match s {
+            "random" => Ok(SceneKind::Random),
+            "chapter" => Ok(SceneKind::Chapter),
+            _ if s.ends_with(".toml") => Ok(SceneKind::File(s.into())),
+            _ => Err(format!(
+                "unknown scene '{}' (expected one of: random, chapter, or a .toml scene file)",
+                s
+            )),
+        }
+    }
+}
+
+fn parse_nonzero(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("must be greater than zero".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(format!("'{}' is not a valid number ({})", s, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolution_from_str() {
+        let res = "640x480".parse::<Resolution>().unwrap();
+        assert_eq!((res.width, res.height), (640, 480));
+
+        for s in &[
+            "640",
+            "640x",
+            "x480",
+            "0x480",
+            "640x0",
+            "-1x480",
+            "640x480x2",
+            "",
+        ] {
+            assert!(s.parse::<Resolution>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn scene_kind_from_str() {
+        assert!(matches!("random".parse(), Ok(SceneKind::Random)));
+        assert!(matches!("chapter".parse(), Ok(SceneKind::Chapter)));
+        match "scenes/chapter.toml".parse() {
+            Ok(SceneKind::File(path)) => assert_eq!(path, PathBuf::from("scenes/chapter.toml")),
+            other => panic!("{:?}", other),
+        }
+        assert!("scenes/chapter.json".parse::<SceneKind>().is_err());
+        assert!("Random".parse::<SceneKind>().is_err());
+    }
+}
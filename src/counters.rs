@@ -0,0 +1,69 @@
+//! Per-thread counters of the work done while rendering.
+//!
+//! These live outside of `render` so that the geometry in `hittable` can
+//! record its intersection tests without depending on the renderer.
+
+use std::cell::Cell;
+
+/// Counts of the work done while rendering
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RayCounts {
+    /// rays cast from the camera
+    pub primary: u64,
+    /// rays cast after bouncing off a surface
+    pub secondary: u64,
+    /// ray-primitive intersection tests
+    pub intersection_tests: u64,
+}
+
+impl RayCounts {
+    const ZERO: RayCounts = RayCounts {
+        primary: 0,
+        secondary: 0,
+        intersection_tests: 0,
+    };
+}
+
+impl std::ops::AddAssign for RayCounts {
+    fn add_assign(&mut self, other: RayCounts) {
+        self.primary += other.primary;
+        self.secondary += other.secondary;
+        self.intersection_tests += other.intersection_tests;
+    }
+}
+
+// Each thread counts its own work, which is periodically collected using
+// [take_counts] (keeping the hot paths free of any synchronization).
+thread_local! {
+    static COUNTS: Cell<RayCounts> = const { Cell::new(RayCounts::ZERO) };
+}
+
+#[inline]
+fn count(f: impl FnOnce(&mut RayCounts)) {
+    COUNTS.with(|c| {
+        let mut counts = c.get();
+        f(&mut counts);
+        c.set(counts);
+    })
+}
+
+/// Record that a ray was cast (`bounce` being the number of surfaces it has
+/// bounced off of so far)
+#[inline]
+pub(crate) fn count_ray(bounce: usize) {
+    count(|c| match bounce {
+        0 => c.primary += 1,
+        _ => c.secondary += 1,
+    })
+}
+
+/// Record a ray-primitive intersection test
+#[inline]
+pub fn count_intersection_test() {
+    count(|c| c.intersection_tests += 1)
+}
+
+/// Return (and reset) the current thread's counts
+pub(crate) fn take_counts() -> RayCounts {
+    COUNTS.with(|c| c.replace(RayCounts::ZERO))
+}
@@ -0,0 +1,36 @@
+use crate::vec3::Vec3;
+
+use super::{Environment, EnvironmentT};
+
+/// A vertical gradient, blending from `bottom` (looking straight down) to
+/// `top` (looking straight up).
+#[derive(Debug)]
+pub struct Gradient {
+    pub bottom: Vec3,
+    pub top: Vec3,
+}
+
+impl Default for Gradient {
+    /// The classic RTIOW blue-white sky
+    fn default() -> Gradient {
+        Gradient {
+            bottom: Vec3::new(1.0, 1.0, 1.0),
+            top: Vec3::new(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+impl Gradient {
+    /// Return a new gradient environment
+    pub fn new_environment(bottom: Vec3, top: Vec3) -> EnvironmentT {
+        Gradient { bottom, top }.into()
+    }
+}
+
+impl Environment for Gradient {
+    fn color(&self, dir: &Vec3) -> Vec3 {
+        let unit_direction = dir.normalize();
+        let t = 0.5 * (unit_direction.y + 1.0);
+        (1.0 - t) * self.bottom + t * self.top
+    }
+}
@@ -0,0 +1,84 @@
+use std::f32::consts::PI;
+use std::io;
+use std::path::Path;
+
+use crate::hdr::{self, HdrImage};
+use crate::vec3::Vec3;
+
+use super::{Environment, EnvironmentT};
+
+/// An equirectangular (latitude / longitude) environment map.
+///
+/// The center of the image lies along -z, and the top row of the image lies
+/// along +y.
+#[derive(Debug)]
+pub struct EnvironmentMap {
+    image: HdrImage,
+    /// rotation about the y axis (in radians)
+    rotation: f32,
+    /// scale factor applied to the map's radiance
+    intensity: f32,
+}
+
+impl EnvironmentMap {
+    /// Load an environment map from a Radiance `.hdr` file, rotated by
+    /// `rotation` degrees about the y axis.
+    pub fn load(
+        path: impl AsRef<Path>,
+        rotation: f32,
+        intensity: f32,
+    ) -> io::Result<EnvironmentMap> {
+        let image = hdr::read(path)?;
+        if image.width == 0 || image.height == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "environment map is empty",
+            ));
+        }
+
+        Ok(EnvironmentMap {
+            image,
+            rotation: rotation.to_radians(),
+            intensity,
+        })
+    }
+
+    /// Load a new environment map. See [EnvironmentMap::load] for details.
+    pub fn new_environment(
+        path: impl AsRef<Path>,
+        rotation: f32,
+        intensity: f32,
+    ) -> io::Result<EnvironmentT> {
+        Ok(EnvironmentMap::load(path, rotation, intensity)?.into())
+    }
+
+    #[inline]
+    fn texel(&self, x: usize, y: usize) -> Vec3 {
+        self.image.pixels[y * self.image.width + x]
+    }
+}
+
+impl Environment for EnvironmentMap {
+    fn color(&self, dir: &Vec3) -> Vec3 {
+        let d = dir.normalize();
+        let (w, h) = (self.image.width, self.image.height);
+
+        // spherical coords -> continuous pixel coords
+        let phi = d.x.atan2(-d.z) - self.rotation;
+        let theta = d.y.clamp(-1., 1.).acos();
+        let u = (phi / (2. * PI) + 0.5).rem_euclid(1.) * w as f32 - 0.5;
+        let v = (theta / PI) * h as f32 - 0.5;
+
+        // bilinear filtering, wrapping horizontally and clamping vertically
+        let (u0, v0) = (u.floor(), v.floor());
+        let (fu, fv) = (u - u0, v - v0);
+        let x0 = (u0 as isize).rem_euclid(w as isize) as usize;
+        let x1 = (x0 + 1) % w;
+        let y0 = (v0.max(0.) as usize).min(h - 1);
+        let y1 = ((v0 + 1.).max(0.) as usize).min(h - 1);
+
+        let top = (1. - fu) * self.texel(x0, y0) + fu * self.texel(x1, y0);
+        let bot = (1. - fu) * self.texel(x0, y1) + fu * self.texel(x1, y1);
+        self.intensity * ((1. - fv) * top + fv * bot)
+    }
+}
@@ -0,0 +1,111 @@
+use crate::vec3::Vec3;
+
+mod gradient;
+mod map;
+mod solid;
+
+pub use gradient::Gradient;
+pub use map::EnvironmentMap;
+pub use solid::SolidColor;
+
+/// Light arriving from "infinitely far away", i.e: whatever a ray sees when it
+/// escapes the scene without hitting anything.
+pub trait Environment: Send + Sync {
+    /// Returns the radiance arriving from direction `dir` (not necessarily
+    /// normalized).
+    fn color(&self, dir: &Vec3) -> Vec3;
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "enum_dispatch")] {
+        pub type EnvironmentT = Environments;
+    } else {
+        pub type EnvironmentT = Box<dyn Environment>;
+    }
+}
+
+macro_rules! impl_ref {
+    ($type:ty) => {
+        impl Environment for $type {
+            fn color(&self, dir: &Vec3) -> Vec3 {
+                (**self).color(dir)
+            }
+        }
+    };
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "enum_dispatch")] {
+        // When using enum dispatch:
+        // - create the enum
+        // - Implements Environment for &enum and &mut enum
+        // - Implements From<variant> for enum
+        // - Implements enum dispatch by implementing Environment on the enum
+        macro_rules! environments {
+            (
+                $(#[$meta:meta])*
+                $(pub)? enum $enum_name:ident {
+                    $($env_name:ident($env_type:ty),)*
+                }
+            ) => {
+                $(#[$meta])*
+                pub enum $enum_name {
+                    $($env_name($env_type),)*
+                }
+
+                impl_ref!(&$enum_name);
+                impl_ref!(&mut $enum_name);
+
+                $(
+                    impl From<$env_type> for $enum_name {
+                        fn from(x: $env_type) -> $enum_name {
+                            $enum_name::$env_name(x)
+                        }
+                    }
+                )*
+
+                impl Environment for $enum_name {
+                    fn color(&self, dir: &Vec3) -> Vec3 {
+                        use self::$enum_name::*;
+                        match self {
+                            $($env_name(x) => x.color(dir),)*
+                        }
+                    }
+                }
+            };
+        }
+    } else {
+        // When using dynamic dispatch:
+        // - Implement Environment for various Box<dyn>, &dyn, and &mut dyn Environment
+        // - Implement From<Environment> for Box<dyn Environment> for each Environment type
+        macro_rules! environments {
+            (
+                $(#[$meta:meta])*
+                $(pub)? enum $enum_name:ident {
+                    $($env_name:ident($env_type:ty),)*
+                }
+            ) => {
+                impl_ref!(Box<dyn Environment>);
+                impl_ref!(&dyn Environment);
+                impl_ref!(&mut dyn Environment);
+
+                $(
+                    impl From<$env_type> for Box<dyn Environment> {
+                        fn from(x: $env_type) -> Box<dyn Environment> {
+                            Box::new(x)
+                        }
+                    }
+                )*
+            };
+        }
+    }
+}
+
+environments! {
+    #[derive(Debug)]
+    pub enum Environments {
+        EnvironmentMap(EnvironmentMap),
+        Gradient(Gradient),
+        SolidColor(SolidColor),
+    }
+}
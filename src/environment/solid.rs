@@ -0,0 +1,22 @@
+use crate::vec3::Vec3;
+
+use super::{Environment, EnvironmentT};
+
+/// The same color, no matter which way you look
+#[derive(Debug)]
+pub struct SolidColor {
+    pub color: Vec3,
+}
+
+impl SolidColor {
+    /// Return a new environment of a single `color`
+    pub fn new_environment(color: Vec3) -> EnvironmentT {
+        SolidColor { color }.into()
+    }
+}
+
+impl Environment for SolidColor {
+    fn color(&self, _dir: &Vec3) -> Vec3 {
+        self.color
+    }
+}
@@ -0,0 +1,209 @@
+//! Reading Radiance `.hdr` (RGBE) images.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use crate::vec3::Vec3;
+
+/// A floating point RGB image, stored row-major, top to bottom
+#[derive(Debug)]
+pub struct HdrImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Vec3>,
+}
+
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Decode a single RGBE pixel
+#[inline]
+fn rgbe_to_rgb([r, g, b, e]: [u8; 4]) -> Vec3 {
+    if e == 0 {
+        return Vec3::new(0., 0., 0.);
+    }
+    // (x + 0.5) / 256 * 2^(e - 128)
+    let f = 2f32.powi(e as i32 - 136);
+    Vec3::new(
+        (r as f32 + 0.5) * f,
+        (g as f32 + 0.5) * f,
+        (b as f32 + 0.5) * f,
+    )
+}
+
+/// Read a single scanline of `width` RGBE pixels (into `scanline`), which may
+/// either be stored flat, or using the (per-channel) adaptive run-length
+/// encoding.
+///
+/// `width` comes from the file's header, so the scanline only grows as pixels
+/// are actually read (rather than trusting it up front).
+fn read_scanline(r: &mut impl Read, width: usize, scanline: &mut Vec<[u8; 4]>) -> io::Result<()> {
+    scanline.clear();
+
+    let mut header = [0; 4];
+    r.read_exact(&mut header)?;
+
+    let is_rle = (8..0x8000).contains(&width) && header[0] == 2 && header[1] == 2;
+    if !is_rle {
+        scanline.push(header);
+        for _ in 1..width {
+            let mut px = [0; 4];
+            r.read_exact(&mut px)?;
+            scanline.push(px);
+        }
+        return Ok(());
+    }
+
+    let encoded_width = ((header[2] as usize) << 8) | header[3] as usize;
+    if encoded_width != width {
+        return Err(invalid_data("mismatched scanline width"));
+    }
+    // (run-length encoded scanlines are less than 0x8000 pixels wide)
+    scanline.resize(width, [0; 4]);
+
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let mut count = [0];
+            r.read_exact(&mut count)?;
+            let count = count[0] as usize;
+
+            if count > 128 {
+                // run of a single value
+                let count = count - 128;
+                if x + count > width {
+                    return Err(invalid_data("bad scanline run length"));
+                }
+                let mut val = [0];
+                r.read_exact(&mut val)?;
+                for px in &mut scanline[x..x + count] {
+                    px[channel] = val[0];
+                }
+                x += count;
+            } else {
+                // run of literal values
+                if count == 0 || x + count > width {
+                    return Err(invalid_data("bad scanline run length"));
+                }
+                for px in &mut scanline[x..x + count] {
+                    let mut val = [0];
+                    r.read_exact(&mut val)?;
+                    px[channel] = val[0];
+                }
+                x += count;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Load a Radiance `.hdr` file.
+///
+/// Only the standard `-Y <height> +X <width>` orientation is supported.
+pub fn read(path: impl AsRef<Path>) -> io::Result<HdrImage> {
+    decode(BufReader::new(File::open(path)?))
+}
+
+/// Decode a Radiance `.hdr` image. See [read] for details.
+pub fn decode(mut r: impl BufRead) -> io::Result<HdrImage> {
+    let mut line = String::new();
+    r.read_line(&mut line)?;
+    if !line.starts_with("#?") {
+        return Err(invalid_data("missing Radiance header"));
+    }
+
+    // header variables, terminated by an empty line
+    loop {
+        line.clear();
+        if r.read_line(&mut line)? == 0 {
+            return Err(invalid_data("unexpected end of header"));
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(invalid_data(format!("unsupported format: {}", format)));
+            }
+        }
+    }
+
+    // resolution string
+    line.clear();
+    r.read_line(&mut line)?;
+    let (height, width): (usize, usize) =
+        match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["-Y", h, "+X", w] => match (h.parse(), w.parse()) {
+                (Ok(h), Ok(w)) => (h, w),
+                _ => return Err(invalid_data("invalid resolution string")),
+            },
+            _ => {
+                return Err(invalid_data(format!(
+                    "unsupported resolution string: {}",
+                    line.trim()
+                )))
+            }
+        };
+
+    if width == 0 || height == 0 {
+        return Err(invalid_data("image is empty"));
+    }
+    if width.checked_mul(height).is_none() {
+        return Err(invalid_data("image is too large"));
+    }
+
+    // (the image only grows as scanlines are read, so a bogus resolution
+    // fails once the data runs out, rather than allocating it all up front)
+    let mut pixels = Vec::new();
+    let mut scanline = Vec::new();
+    for _ in 0..height {
+        read_scanline(&mut r, width, &mut scanline)?;
+        pixels.extend(scanline.iter().map(|&px| rgbe_to_rgb(px)));
+    }
+
+    Ok(HdrImage {
+        width,
+        height,
+        pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_images_are_rejected() {
+        for res in &["-Y 1 +X 0", "-Y 0 +X 1", "-Y 0 +X 0"] {
+            let file = format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n{}\n", res);
+            let err = decode(file.as_bytes()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn huge_images_are_rejected() {
+        let file = format!("#?RADIANCE\n\n-Y {} +X {}\n", usize::MAX, 2);
+        assert!(decode(file.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn truncated_images_are_rejected() {
+        // resolutions which would be far too large to allocate, followed by
+        // only a couple of pixels
+        for res in &[
+            "-Y 4611686018427387904 +X 1",
+            "-Y 1 +X 4611686018427387904",
+            "-Y 100000 +X 100000",
+        ] {
+            let mut file = format!("#?RADIANCE\n\n{}\n", res).into_bytes();
+            file.extend_from_slice(&[1, 2, 3, 128, 4, 5, 6, 128]);
+            let err = decode(&file[..]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof, "{}", res);
+        }
+    }
+}
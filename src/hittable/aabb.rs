@@ -0,0 +1,88 @@
+use std::ops::Range;
+
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+/// An Axis-Aligned Bounding Box
+#[derive(Debug, Copy, Clone)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// Create a new bounding box spanning from `min` to `max`
+    pub fn new(min: Vec3, max: Vec3) -> Aabb {
+        Aabb { min, max }
+    }
+
+    /// Returns a bounding box that contains nothing (i.e: the identity for
+    /// [Aabb::union])
+    pub fn empty() -> Aabb {
+        Aabb {
+            min: Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Vec3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    /// Returns the smallest bounding box containing both `self` and `other`
+    #[inline]
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(&other.min),
+            max: self.max.max(&other.max),
+        }
+    }
+
+    /// Returns the smallest bounding box containing both `self` and `p`
+    #[inline]
+    pub fn grow(&self, p: &Vec3) -> Aabb {
+        Aabb {
+            min: self.min.min(p),
+            max: self.max.max(p),
+        }
+    }
+
+    /// Center point of the box
+    #[inline]
+    pub fn centroid(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    /// Surface area of the box (zero if the box is empty)
+    #[inline]
+    pub fn surface_area(&self) -> f32 {
+        let d = self.max - self.min;
+        if d.x < 0. || d.y < 0. || d.z < 0. {
+            return 0.;
+        }
+        2. * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    /// Slab test. `inv_dir` should be the component-wise reciprocal of the
+    /// ray's direction (which is worth precomputing when testing many boxes).
+    /// Returns the distance along the ray at which it enters the box, or None
+    /// if it doesn't hit the box within `t_range`.
+    #[inline]
+    pub fn hit(&self, r: &Ray, inv_dir: &Vec3, t_range: &Range<f32>) -> Option<f32> {
+        let mut t_min = t_range.start;
+        let mut t_max = t_range.end;
+        for axis in 0..3 {
+            let t0 = (self.min[axis] - r.origin[axis]) * inv_dir[axis];
+            let t1 = (self.max[axis] - r.origin[axis]) * inv_dir[axis];
+            let (t0, t1) = if inv_dir[axis] < 0. {
+                (t1, t0)
+            } else {
+                (t0, t1)
+            };
+            // f32::max / f32::min ignore NaNs (from 0 * inf), which keeps
+            // the test conservative for rays lying in a slab's plane
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+}
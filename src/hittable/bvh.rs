@@ -0,0 +1,404 @@
+use std::ops::Range;
+
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+use super::{Aabb, HitRecord, Hittable};
+
+/// Number of buckets used when evaluating candidate SAH splits
+const NUM_BINS: usize = 12;
+/// Nodes with at most this many primitives may become leaves
+const MAX_LEAF_SIZE: usize = 4;
+/// Relative cost of traversing a node vs. intersecting a primitive
+const TRAVERSAL_COST: f32 = 0.5;
+/// Past this depth, nodes are forced to become leaves. This bounds the size of
+/// the traversal stack.
+const MAX_DEPTH: usize = 48;
+
+/// A node in the flattened tree.
+///
+/// Interior nodes are immediately followed by their first child, so only the
+/// index of the second child needs to be stored.
+#[derive(Debug)]
+struct Node {
+    bounds: Aabb,
+    /// leaf: index of the node's first primitive
+    /// interior: index of the node's second child
+    offset: usize,
+    /// number of primitives in the leaf (0 for interior nodes)
+    count: usize,
+    /// axis the node's children were split along
+    axis: usize,
+}
+
+/// Primitive info used while building the tree
+struct BuildPrim {
+    index: usize,
+    bounds: Aabb,
+    centroid: Vec3,
+}
+
+/// A Bounding Volume Hierarchy, built using the Surface Area Heuristic.
+///
+/// Primitives without a bounding box (e.g: [InfPlane](super::InfPlane)) are
+/// kept outside the tree, and are tested against every ray.
+#[derive(Debug)]
+pub struct Bvh<H> {
+    nodes: Vec<Node>,
+    /// bounded primitives, ordered such that each leaf's primitives are
+    /// contiguous
+    prims: Vec<H>,
+    /// primitives that can't be placed in the tree
+    unbounded: Vec<H>,
+    /// index of each of `prims` in the list the BVH was built from (which is
+    /// reported as the [HitRecord::object] hit)
+    prim_ids: Vec<usize>,
+    /// index of each of `unbounded` in the list the BVH was built from
+    unbounded_ids: Vec<usize>,
+}
+
+impl<H: Hittable> Bvh<H> {
+    /// Build a new BVH over the given primitives
+    pub fn new(hittables: Vec<H>) -> Bvh<H> {
+        let mut bounded = Vec::new();
+        let mut unbounded = Vec::new();
+        let mut build_prims = Vec::new();
+        let mut bounded_ids = Vec::new();
+        let mut unbounded_ids = Vec::new();
+        for (id, h) in hittables.into_iter().enumerate() {
+            match h.bounding_box() {
+                Some(bounds) => {
+                    build_prims.push(BuildPrim {
+                        index: bounded.len(),
+                        bounds,
+                        centroid: bounds.centroid(),
+                    });
+                    bounded.push(Some(h));
+                    bounded_ids.push(id);
+                }
+                None => {
+                    unbounded.push(h);
+                    unbounded_ids.push(id);
+                }
+            }
+        }
+
+        let mut nodes = Vec::new();
+        if !build_prims.is_empty() {
+            build(&mut nodes, &mut build_prims, 0, 0);
+        }
+
+        // shuffle the primitives into leaf order
+        let prims = build_prims
+            .iter()
+            .map(|p| bounded[p.index].take().unwrap())
+            .collect();
+        let prim_ids = build_prims.iter().map(|p| bounded_ids[p.index]).collect();
+
+        Bvh {
+            nodes,
+            prims,
+            unbounded,
+            prim_ids,
+            unbounded_ids,
+        }
+    }
+}
+
+/// Recursively build the subtree over `prims` (which start at index `start` in
+/// the final primitive array), returning the index of the subtree's root node.
+fn build(nodes: &mut Vec<Node>, prims: &mut [BuildPrim], start: usize, depth: usize) -> usize {
+    let bounds = prims.iter().fold(Aabb::empty(), |b, p| b.union(&p.bounds));
+    let centroid_bounds = prims.iter().fold(Aabb::empty(), |b, p| b.grow(&p.centroid));
+
+    let idx = nodes.len();
+    nodes.push(Node {
+        bounds,
+        offset: start,
+        count: prims.len(),
+        axis: 0,
+    });
+
+    if prims.len() == 1 || depth >= MAX_DEPTH {
+        return idx;
+    }
+
+    // split along the axis with the largest centroid spread
+    let extent = centroid_bounds.max - centroid_bounds.min;
+    let axis = if extent.x > extent.y && extent.x > extent.z {
+        0
+    } else if extent.y > extent.z {
+        1
+    } else {
+        2
+    };
+
+    if extent[axis] <= 0. {
+        // all centroids coincide, there's no useful way to split them
+        return idx;
+    }
+
+    // bin primitives by centroid
+    let bin_of = |p: &BuildPrim| {
+        let rel = (p.centroid[axis] - centroid_bounds.min[axis]) / extent[axis];
+        ((rel * NUM_BINS as f32) as usize).min(NUM_BINS - 1)
+    };
+
+    let mut bin_counts = [0usize; NUM_BINS];
+    let mut bin_bounds = [Aabb::empty(); NUM_BINS];
+    for p in prims.iter() {
+        let b = bin_of(p);
+        bin_counts[b] += 1;
+        bin_bounds[b] = bin_bounds[b].union(&p.bounds);
+    }
+
+    // evaluate the SAH cost of splitting after each bin
+    let mut best_split = 0;
+    let mut best_cost = f32::INFINITY;
+    for split in 1..NUM_BINS {
+        let (left, right) = (0..split, split..NUM_BINS);
+
+        let count_l: usize = bin_counts[left.clone()].iter().sum();
+        let count_r: usize = bin_counts[right.clone()].iter().sum();
+        if count_l == 0 || count_r == 0 {
+            continue;
+        }
+
+        let area = |r: Range<usize>| {
+            bin_bounds[r]
+                .iter()
+                .fold(Aabb::empty(), |b, x| b.union(x))
+                .surface_area()
+        };
+
+        let cost = TRAVERSAL_COST
+            + (count_l as f32 * area(left) + count_r as f32 * area(right)) / bounds.surface_area();
+        if cost < best_cost {
+            best_cost = cost;
+            best_split = split;
+        }
+    }
+
+    let leaf_cost = prims.len() as f32;
+    if prims.len() <= MAX_LEAF_SIZE && leaf_cost <= best_cost {
+        return idx;
+    }
+
+    // partition primitives about the chosen split
+    let mut mid = 0;
+    for i in 0..prims.len() {
+        if bin_of(&prims[i]) < best_split {
+            prims.swap(i, mid);
+            mid += 1;
+        }
+    }
+
+    let (left, right) = prims.split_at_mut(mid);
+    build(nodes, left, start, depth + 1);
+    let second = build(nodes, right, start + mid, depth + 1);
+
+    nodes[idx].offset = second;
+    nodes[idx].count = 0;
+    nodes[idx].axis = axis;
+    idx
+}
+
+impl<H: Hittable> Hittable for Bvh<H> {
+    /// Returns the HitRecord of the closest hittable object
+    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord<'_>> {
+        let mut temp_rec = self.unbounded.hit(r, t_range.clone()).map(|mut rec| {
+            rec.object = self.unbounded_ids[rec.object];
+            rec
+        });
+        let mut closest_so_far = temp_rec.as_ref().map_or(t_range.end, |rec| rec.t);
+
+        if self.nodes.is_empty() {
+            return temp_rec;
+        }
+
+        let inv_dir = Vec3::new(1. / r.direction.x, 1. / r.direction.y, 1. / r.direction.z);
+
+        let mut stack = [0usize; MAX_DEPTH + 1];
+        let mut stack_len = 1;
+        while stack_len > 0 {
+            stack_len -= 1;
+            let idx = stack[stack_len];
+            let node = &self.nodes[idx];
+
+            let t_range = t_range.start..closest_so_far;
+            if node.bounds.hit(r, &inv_dir, &t_range).is_none() {
+                continue;
+            }
+
+            if node.count > 0 {
+                let prims = node.offset..node.offset + node.count;
+                for (i, prim) in prims.clone().zip(&self.prims[prims]) {
+                    if let Some(mut rec) = prim.hit(r, t_range.start..closest_so_far) {
+                        closest_so_far = rec.t;
+                        rec.object = self.prim_ids[i];
+                        temp_rec = Some(rec);
+                    }
+                }
+            } else {
+                // visit the nearer child first, to tighten `closest_so_far`
+                // as early as possible
+                let (near, far) = if r.direction[node.axis] < 0. {
+                    (node.offset, idx + 1)
+                } else {
+                    (idx + 1, node.offset)
+                };
+                stack[stack_len] = far;
+                stack[stack_len + 1] = near;
+                stack_len += 2;
+            }
+        }
+
+        temp_rec
+    }
+
+    /// Returns as soon as any object is hit, without ordering the traversal
+    fn occluded(&self, r: &Ray, t_range: Range<f32>) -> bool {
+        if self.unbounded.occluded(r, t_range.clone()) {
+            return true;
+        }
+
+        if self.nodes.is_empty() {
+            return false;
+        }
+
+        let inv_dir = Vec3::new(1. / r.direction.x, 1. / r.direction.y, 1. / r.direction.z);
+
+        let mut stack = [0usize; MAX_DEPTH + 1];
+        let mut stack_len = 1;
+        while stack_len > 0 {
+            stack_len -= 1;
+            let idx = stack[stack_len];
+            let node = &self.nodes[idx];
+
+            if node.bounds.hit(r, &inv_dir, &t_range).is_none() {
+                continue;
+            }
+
+            if node.count > 0 {
+                let prims = &self.prims[node.offset..node.offset + node.count];
+                if prims.iter().any(|p| p.occluded(r, t_range.clone())) {
+                    return true;
+                }
+            } else {
+                stack[stack_len] = node.offset;
+                stack[stack_len + 1] = idx + 1;
+                stack_len += 2;
+            }
+        }
+
+        false
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        if !self.unbounded.is_empty() {
+            return None;
+        }
+
+        Some(self.nodes.first().map_or(Aabb::empty(), |root| root.bounds))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::hittable::{HittableT, InfPlane, Sphere, Triangle};
+    use crate::material::Lambertian;
+
+    fn random_vec3(rng: &mut StdRng, scale: f32) -> Vec3 {
+        Vec3::new(
+            rng.gen_range(-scale, scale),
+            rng.gen_range(-scale, scale),
+            rng.gen_range(-scale, scale),
+        )
+    }
+
+    /// A jumble of (possibly overlapping) spheres and triangles, along with an
+    /// unbounded plane
+    fn scene(seed: u64) -> Vec<HittableT> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let material = || Lambertian::new_material(Vec3::new(0.5, 0.5, 0.5));
+
+        let mut scene = Vec::new();
+        for i in 0..200 {
+            let center = random_vec3(&mut rng, 10.);
+            if i == 100 {
+                scene.push(InfPlane::new_hittable(
+                    Vec3::new(0., -12., 0.),
+                    Vec3::new(0., 1., 0.),
+                    material(),
+                ));
+            } else if i % 2 == 0 {
+                scene.push(Sphere::new_hittable(
+                    center,
+                    rng.gen_range(0.1, 1.),
+                    material(),
+                ));
+            } else {
+                scene.push(Triangle::new_hittable(
+                    center + random_vec3(&mut rng, 1.),
+                    center + random_vec3(&mut rng, 1.),
+                    center + random_vec3(&mut rng, 1.),
+                    material(),
+                ));
+            }
+        }
+        scene
+    }
+
+    #[test]
+    fn hits_match_a_linear_scan() {
+        let linear = scene(1);
+        let bvh = Bvh::new(scene(1));
+        assert!(bvh.bounding_box().is_none());
+
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut hits = 0;
+        for i in 0..2000 {
+            let origin = random_vec3(&mut rng, 15.);
+            // (include some axis-aligned rays, with infinite inverse components)
+            let direction = match i % 4 {
+                0 => Vec3::new(0., 0., 1.),
+                _ => random_vec3(&mut rng, 1.),
+            };
+            let r = Ray::new(origin, direction);
+            let t_range = 0.001..rng.gen_range(1., 50.);
+
+            let expected = linear.hit(&r, t_range.clone());
+            let actual = bvh.hit(&r, t_range.clone());
+            match (&expected, &actual) {
+                (None, None) => {}
+                (Some(a), Some(b)) => {
+                    hits += 1;
+                    assert_eq!(a.t, b.t);
+                    assert_eq!(a.object, b.object);
+                }
+                _ => panic!(
+                    "ray {}: {:?} != {:?}",
+                    i,
+                    expected.map(|r| r.t),
+                    actual.map(|r| r.t)
+                ),
+            }
+            assert_eq!(expected.is_some(), bvh.occluded(&r, t_range.clone()));
+            assert_eq!(expected.is_some(), linear.occluded(&r, t_range));
+        }
+        // (make sure the test actually tests something)
+        assert!(hits > 200, "{}", hits);
+    }
+
+    #[test]
+    fn empty_bvh() {
+        let bvh = Bvh::<HittableT>::new(Vec::new());
+        let r = Ray::new(Vec3::new(0., 0., 0.), Vec3::new(0., 0., 1.));
+        assert!(bvh.hit(&r, 0.001..f32::MAX).is_none());
+        assert!(!bvh.occluded(&r, 0.001..f32::MAX));
+    }
+}
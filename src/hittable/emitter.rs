@@ -0,0 +1,53 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::ray::Ray;
+
+use super::{Aabb, HitRecord, Hittable, HittableT};
+
+/// A light-emitting object, whose primitives are also sampled directly as
+/// scene lights (numbered consecutively, starting from `first_light`).
+///
+/// Hits on the object are tagged with the index of the matching light, so the
+/// renderer can tell which light a scattered ray happened to find.
+pub struct Emitter {
+    object: Box<HittableT>,
+    first_light: usize,
+}
+
+impl Emitter {
+    /// Wrap `object`, whose primitives are sampled by the scene's lights
+    /// `first_light..`
+    pub fn new_hittable(object: HittableT, first_light: usize) -> HittableT {
+        Emitter {
+            object: Box::new(object),
+            first_light,
+        }
+        .into()
+    }
+}
+
+// (dynamically dispatched hittables don't implement Debug)
+impl fmt::Debug for Emitter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Emitter")
+            .field("first_light", &self.first_light)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Hittable for Emitter {
+    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord<'_>> {
+        let mut rec = self.object.hit(r, t_range)?;
+        rec.light = Some(self.first_light + rec.primitive);
+        Some(rec)
+    }
+
+    fn occluded(&self, r: &Ray, t_range: Range<f32>) -> bool {
+        self.object.occluded(r, t_range)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.object.bounding_box()
+    }
+}
@@ -1,10 +1,12 @@
 use std::ops::Range;
 
+use crate::counters::count_intersection_test;
 use crate::material::MaterialT;
 use crate::ray::Ray;
+use crate::util::orthonormal_basis;
 use crate::vec3::Vec3;
 
-use super::{HitRecord, Hittable, HittableT};
+use super::{Aabb, HitRecord, Hittable, HittableT};
 
 /// A infinitely flat plane.
 #[derive(Debug)]
@@ -27,20 +29,39 @@ impl InfPlane {
 }
 
 impl Hittable for InfPlane {
-    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord<'_>> {
+        count_intersection_test();
         // lightly modified from
         // https://samsymons.com/blog/math-notes-ray-plane-intersection/
         let denominator = self.normal.dot(&r.direction);
         let t = (self.center - r.origin).dot(&self.normal) / denominator;
         if t_range.contains(&t) {
+            let p = r.point_at_param(t);
+            // world-space coordinates along the plane, from its center
+            let (tangent, bitangent) = orthonormal_basis(&self.normal);
+            let d = p - self.center;
             return Some(HitRecord {
                 t,
-                p: r.point_at_param(t),
+                p,
                 normal: self.normal,
+                uv: (d.dot(&tangent), d.dot(&bitangent)),
                 material: &self.material,
+                object: 0,
+                primitive: 0,
+                light: None,
             });
         }
 
         None
     }
+
+    fn occluded(&self, r: &Ray, t_range: Range<f32>) -> bool {
+        count_intersection_test();
+        let t = (self.center - r.origin).dot(&self.normal) / self.normal.dot(&r.direction);
+        t_range.contains(&t)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
 }
@@ -0,0 +1,185 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::counters::count_intersection_test;
+use crate::material::MaterialT;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+use super::triangle::{bounds, intersect};
+use super::{Aabb, Bvh, HitRecord, Hittable, HittableT};
+
+/// Vertex / index / normal / texcoord buffers shared between all the faces of
+/// a mesh.
+#[derive(Debug)]
+struct MeshData {
+    positions: Vec<Vec3>,
+    /// per-vertex normals (empty if the mesh should be flat-shaded)
+    normals: Vec<Vec3>,
+    /// per-vertex texture coordinates (empty if the mesh doesn't have any)
+    texcoords: Vec<[f32; 2]>,
+    indices: Vec<[usize; 3]>,
+    material: MaterialT,
+}
+
+/// A single face of a [TriangleMesh]
+#[derive(Debug)]
+struct MeshFace {
+    mesh: Arc<MeshData>,
+    face: usize,
+}
+
+impl MeshFace {
+    #[inline]
+    fn vertices(&self) -> (&Vec3, &Vec3, &Vec3) {
+        let [i0, i1, i2] = self.mesh.indices[self.face];
+        let p = &self.mesh.positions;
+        (&p[i0], &p[i1], &p[i2])
+    }
+}
+
+impl Hittable for MeshFace {
+    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord<'_>> {
+        let (v0, v1, v2) = self.vertices();
+        count_intersection_test();
+        let (t, u, v) = intersect(r, &t_range, v0, v1, v2)?;
+
+        let normal = if self.mesh.normals.is_empty() {
+            (*v1 - *v0).cross(&(*v2 - *v0)).normalize()
+        } else {
+            // interpolate the vertex normals across the face
+            let [i0, i1, i2] = self.mesh.indices[self.face];
+            let n = &self.mesh.normals;
+            ((1. - u - v) * n[i0] + u * n[i1] + v * n[i2]).normalize()
+        };
+
+        let uv = if self.mesh.texcoords.is_empty() {
+            // (barycentric coordinates)
+            (u, v)
+        } else {
+            // interpolate the vertex texcoords across the face
+            let [i0, i1, i2] = self.mesh.indices[self.face];
+            let tc = &self.mesh.texcoords;
+            let w = 1. - u - v;
+            (
+                w * tc[i0][0] + u * tc[i1][0] + v * tc[i2][0],
+                w * tc[i0][1] + u * tc[i1][1] + v * tc[i2][1],
+            )
+        };
+
+        Some(HitRecord {
+            t,
+            p: r.point_at_param(t),
+            normal,
+            uv,
+            material: &self.mesh.material,
+            object: 0,
+            primitive: self.face,
+            light: None,
+        })
+    }
+
+    fn occluded(&self, r: &Ray, t_range: Range<f32>) -> bool {
+        let (v0, v1, v2) = self.vertices();
+        count_intersection_test();
+        intersect(r, &t_range, v0, v1, v2).is_some()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let (v0, v1, v2) = self.vertices();
+        Some(bounds(v0, v1, v2))
+    }
+}
+
+/// An indexed triangle mesh, with optional per-vertex normals and texture
+/// coordinates.
+///
+/// Faces are stored in their own [Bvh], so the mesh as a whole can be cheaply
+/// placed into a larger scene.
+#[derive(Debug)]
+pub struct TriangleMesh {
+    faces: Bvh<MeshFace>,
+}
+
+impl TriangleMesh {
+    /// Create a new mesh out of the triangles described by `indices` into the
+    /// `positions` buffer.
+    ///
+    /// `normals` may either be empty (in which case the mesh is flat-shaded),
+    /// or contain a normal for each position. Likewise, `texcoords` may either
+    /// be empty (in which case faces use their barycentric coordinates), or
+    /// contain texture coordinates for each position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any index is out of bounds, or if the number of normals or
+    /// texcoords doesn't match the number of positions.
+    pub fn new(
+        positions: Vec<Vec3>,
+        normals: Vec<Vec3>,
+        texcoords: Vec<[f32; 2]>,
+        indices: Vec<[usize; 3]>,
+        material: MaterialT,
+    ) -> TriangleMesh {
+        assert!(
+            normals.is_empty() || normals.len() == positions.len(),
+            "mesh has {} positions, but {} normals",
+            positions.len(),
+            normals.len()
+        );
+        assert!(
+            texcoords.is_empty() || texcoords.len() == positions.len(),
+            "mesh has {} positions, but {} texcoords",
+            positions.len(),
+            texcoords.len()
+        );
+        assert!(
+            indices.iter().flatten().all(|&i| i < positions.len()),
+            "mesh index out of bounds"
+        );
+
+        let mesh = Arc::new(MeshData {
+            positions,
+            normals,
+            texcoords,
+            indices,
+            material,
+        });
+
+        let faces = (0..mesh.indices.len())
+            .map(|face| MeshFace {
+                mesh: Arc::clone(&mesh),
+                face,
+            })
+            .collect();
+
+        TriangleMesh {
+            faces: Bvh::new(faces),
+        }
+    }
+
+    /// Create a new mesh. See [TriangleMesh::new] for details.
+    pub fn new_hittable(
+        positions: Vec<Vec3>,
+        normals: Vec<Vec3>,
+        texcoords: Vec<[f32; 2]>,
+        indices: Vec<[usize; 3]>,
+        material: MaterialT,
+    ) -> HittableT {
+        TriangleMesh::new(positions, normals, texcoords, indices, material).into()
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord<'_>> {
+        self.faces.hit(r, t_range)
+    }
+
+    fn occluded(&self, r: &Ray, t_range: Range<f32>) -> bool {
+        self.faces.occluded(r, t_range)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.faces.bounding_box()
+    }
+}
@@ -4,11 +4,27 @@ use crate::material::MaterialT;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 
+mod aabb;
+mod bvh;
+mod emitter;
 mod infplane;
+mod mesh;
+mod quad;
 mod sphere;
+mod triangle;
 
+pub use aabb::Aabb;
+pub use bvh::Bvh;
+pub use emitter::Emitter;
 pub use infplane::InfPlane;
+pub use mesh::TriangleMesh;
+pub use quad::Quad;
 pub use sphere::Sphere;
+pub use triangle::Triangle;
+
+// the bare intersection tests, for sampling lights of the same shapes
+pub(crate) use quad::intersect as intersect_quad;
+pub(crate) use triangle::intersect as intersect_triangle;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "enum_dispatch")] {
@@ -27,23 +43,54 @@ pub struct HitRecord<'m> {
     pub p: Vec3,
     /// Hit Normal
     pub normal: Vec3,
+    /// Surface (texture) coordinates of the hit point
+    pub uv: (f32, f32),
     /// Material
     pub material: &'m MaterialT,
+    /// Index of the hit object within the world. Primitives set this to 0,
+    /// and containers (e.g: [Bvh]) overwrite it with the index of whichever
+    /// of their objects was hit.
+    pub object: usize,
+    /// Index of the hit primitive within its object (e.g: a mesh's face), or
+    /// 0 for objects made of a single primitive
+    pub primitive: usize,
+    /// Index of the scene light matching the hit primitive, if it's sampled
+    /// directly (see [Emitter])
+    pub light: Option<usize>,
 }
 
 /// Anything that can be Hit by a ray
 pub trait Hittable: Send + Sync {
     /// Check if object is hit by [Ray] `r`.
     /// Returns None if no hit occurred, or Some(HitRecord) otherwise.
-    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord>;
+    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord<'_>>;
+
+    /// Check if [Ray] `r` hits _anything_ within `t_range` (e.g: for shadow
+    /// rays). Unlike [Hittable::hit], this may stop at the first hit found,
+    /// instead of searching for the closest one.
+    fn occluded(&self, r: &Ray, t_range: Range<f32>) -> bool {
+        self.hit(r, t_range).is_some()
+    }
+
+    /// Returns a bounding box enclosing the object, or None if the object is
+    /// unbounded (e.g: an infinite plane).
+    fn bounding_box(&self) -> Option<Aabb>;
 }
 
 macro_rules! impl_ref {
     ($type:ty) => {
         impl Hittable for $type {
-            fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord> {
+            fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord<'_>> {
                 (**self).hit(r, t_range)
             }
+
+            fn occluded(&self, r: &Ray, t_range: Range<f32>) -> bool {
+                (**self).occluded(r, t_range)
+            }
+
+            fn bounding_box(&self) -> Option<Aabb> {
+                (**self).bounding_box()
+            }
         }
     };
 }
@@ -53,7 +100,7 @@ cfg_if::cfg_if! {
         // When using enum dispatch:
         // - create the enum
         // - Implements Hittable for &enum and &mut enum
-        // - Implements Into<enum> for each enum variant
+        // - Implements From<variant> for enum
         // - Implements enum dispatch by implementing Hittable on the enum
         macro_rules! hittables {
             (
@@ -71,27 +118,41 @@ cfg_if::cfg_if! {
                 impl_ref!(&mut $enum_name);
 
                 $(
-                    impl Into<$enum_name> for $hit_type {
-                        fn into(self) -> $enum_name {
-                            $enum_name::$hit_name(self)
+                    impl From<$hit_type> for $enum_name {
+                        fn from(x: $hit_type) -> $enum_name {
+                            $enum_name::$hit_name(x)
                         }
                     }
                 )*
 
                 impl Hittable for $enum_name {
-                    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord> {
+                    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord<'_>> {
                         use self::$enum_name::*;
                         match self {
                             $($hit_name(x) => x.hit(r, t_range),)*
                         }
                     }
+
+                    fn occluded(&self, r: &Ray, t_range: Range<f32>) -> bool {
+                        use self::$enum_name::*;
+                        match self {
+                            $($hit_name(x) => x.occluded(r, t_range),)*
+                        }
+                    }
+
+                    fn bounding_box(&self) -> Option<Aabb> {
+                        use self::$enum_name::*;
+                        match self {
+                            $($hit_name(x) => x.bounding_box(),)*
+                        }
+                    }
                 }
             };
         }
     } else {
         // When using dynamic dispatch:
         // - Implement Hittable for various Box<dyn>, &dyn, and &mut dyn Hittable
-        // - Implement Into<Box<dyn Hittable>> for each Hittable type
+        // - Implement From<Hittable> for Box<dyn Hittable> for each Hittable type
         macro_rules! hittables {
             (
                 $(#[$meta:meta])*
@@ -104,9 +165,9 @@ cfg_if::cfg_if! {
                 impl_ref!(&mut dyn Hittable);
 
                 $(
-                    impl Into<Box<dyn Hittable>> for $hit_type {
-                        fn into(self) -> Box<dyn Hittable> {
-                            Box::new(self)
+                    impl From<$hit_type> for Box<dyn Hittable> {
+                        fn from(x: $hit_type) -> Box<dyn Hittable> {
+                            Box::new(x)
                         }
                     }
                 )*
@@ -120,6 +181,10 @@ hittables! {
     pub enum Hittables {
         Sphere(Sphere),
         InfPlane(InfPlane),
+        Quad(Quad),
+        Triangle(Triangle),
+        TriangleMesh(TriangleMesh),
+        Emitter(Emitter),
     }
 }
 
@@ -128,34 +193,58 @@ hittables! {
 
 impl<H: Hittable> Hittable for &[H] {
     /// Returns the HitRecord of the closest hittable object
-    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord<'_>> {
         let mut temp_rec = None;
         let mut closest_so_far = t_range.end;
 
-        for hittable in self.iter() {
-            if let Some(rec) = hittable.hit(r, t_range.start..closest_so_far) {
+        for (i, hittable) in self.iter().enumerate() {
+            if let Some(mut rec) = hittable.hit(r, t_range.start..closest_so_far) {
                 closest_so_far = rec.t;
+                rec.object = i;
                 temp_rec = Some(rec);
             }
         }
 
         temp_rec
     }
+
+    /// Returns as soon as any object is hit
+    fn occluded(&self, r: &Ray, t_range: Range<f32>) -> bool {
+        self.iter().any(|h| h.occluded(r, t_range.clone()))
+    }
+
+    /// Returns the union of all the objects' bounding boxes
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.iter()
+            .try_fold(Aabb::empty(), |b, h| Some(b.union(&h.bounding_box()?)))
+    }
 }
 
 impl<H: Hittable> Hittable for Vec<H> {
     /// Returns the HitRecord of the closest hittable object
-    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord<'_>> {
         let mut temp_rec = None;
         let mut closest_so_far = t_range.end;
 
-        for hittable in self.iter() {
-            if let Some(rec) = hittable.hit(r, t_range.start..closest_so_far) {
+        for (i, hittable) in self.iter().enumerate() {
+            if let Some(mut rec) = hittable.hit(r, t_range.start..closest_so_far) {
                 closest_so_far = rec.t;
+                rec.object = i;
                 temp_rec = Some(rec);
             }
         }
 
         temp_rec
     }
+
+    /// Returns as soon as any object is hit
+    fn occluded(&self, r: &Ray, t_range: Range<f32>) -> bool {
+        self.iter().any(|h| h.occluded(r, t_range.clone()))
+    }
+
+    /// Returns the union of all the objects' bounding boxes
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.iter()
+            .try_fold(Aabb::empty(), |b, h| Some(b.union(&h.bounding_box()?)))
+    }
 }
@@ -0,0 +1,91 @@
+use std::ops::Range;
+
+use crate::counters::count_intersection_test;
+use crate::material::MaterialT;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+use super::{Aabb, HitRecord, Hittable, HittableT};
+
+/// A parallelogram, spanned by edges `u` and `v` from corner `q`.
+///
+/// The quad's normal faces the side from which `u` to `v` is a
+/// counter-clockwise turn.
+#[derive(Debug)]
+pub struct Quad {
+    pub q: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub material: MaterialT,
+}
+
+impl Quad {
+    /// Create a new quad with corner `q`, and edges `u` and `v`
+    pub fn new_hittable(q: Vec3, u: Vec3, v: Vec3, material: MaterialT) -> HittableT {
+        Quad { q, u, v, material }.into()
+    }
+}
+
+/// Ray-quad intersection.
+/// Returns the `t` along the ray, and the `(a, b)` coordinates of the hit
+/// point, such that it lies at `q + a * u + b * v`.
+#[inline]
+pub(crate) fn intersect(
+    r: &Ray,
+    t_range: &Range<f32>,
+    q: &Vec3,
+    u: &Vec3,
+    v: &Vec3,
+) -> Option<(f32, f32, f32)> {
+    let n = u.cross(v);
+    let denominator = n.dot(&r.direction);
+    // ray is parallel to the quad
+    if denominator.abs() < 1e-8 {
+        return None;
+    }
+
+    let t = (*q - r.origin).dot(&n) / denominator;
+    if !t_range.contains(&t) {
+        return None;
+    }
+
+    // express the hit point in terms of the quad's edges
+    let p = r.point_at_param(t) - *q;
+    let w = n / n.dot(&n);
+    let a = w.dot(&p.cross(v));
+    let b = w.dot(&u.cross(&p));
+    if !(0. ..=1.).contains(&a) || !(0. ..=1.).contains(&b) {
+        return None;
+    }
+
+    Some((t, a, b))
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord<'_>> {
+        count_intersection_test();
+        let (t, a, b) = intersect(r, &t_range, &self.q, &self.u, &self.v)?;
+        Some(HitRecord {
+            t,
+            p: r.point_at_param(t),
+            normal: self.u.cross(&self.v).normalize(),
+            uv: (a, b),
+            material: &self.material,
+            object: 0,
+            primitive: 0,
+            light: None,
+        })
+    }
+
+    fn occluded(&self, r: &Ray, t_range: Range<f32>) -> bool {
+        count_intersection_test();
+        intersect(r, &t_range, &self.q, &self.u, &self.v).is_some()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let (q, u, v) = (self.q, self.u, self.v);
+        let (a, b) = (q.min(&(q + u + v)), q.max(&(q + u + v)));
+        let (c, d) = ((q + u).min(&(q + v)), (q + u).max(&(q + v)));
+        Some(Aabb::new(a.min(&c), b.max(&d)))
+    }
+}
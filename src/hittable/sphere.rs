@@ -1,10 +1,12 @@
+use std::f32::consts::PI;
 use std::ops::Range;
 
+use crate::counters::count_intersection_test;
 use crate::material::MaterialT;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 
-use super::{HitRecord, Hittable, HittableT};
+use super::{Aabb, HitRecord, Hittable, HittableT};
 
 /// A Sphere. You know what a Sphere is, right?
 #[derive(Debug)]
@@ -26,8 +28,18 @@ impl Sphere {
     }
 }
 
+/// Returns the (u, v) coordinates of a point `d` on the unit sphere, where u
+/// is the angle around the y axis (starting from -x), and v is the angle from
+/// -y up to +y, both mapped to [0, 1].
+fn sphere_uv(d: &Vec3) -> (f32, f32) {
+    let u = ((-d.z).atan2(d.x) + PI) / (2. * PI);
+    let v = (-d.y).clamp(-1., 1.).acos() / PI;
+    (u, v)
+}
+
 impl Hittable for Sphere {
-    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord<'_>> {
+        count_intersection_test();
         let oc = r.origin - self.center;
         let a = Vec3::dot(&r.direction, &r.direction);
         let b = 2.0 * Vec3::dot(&oc, &r.direction);
@@ -42,7 +54,8 @@ impl Hittable for Sphere {
                         let t = root;
                         let p = r.point_at_param(t);
                         let normal = (p - self.center) / self.radius;
-                        return Some(HitRecord{ t, p, normal, material: &self.material });
+                        let uv = sphere_uv(&((p - self.center) / self.radius.abs()));
+                        return Some(HitRecord{ t, p, normal, uv, material: &self.material, object: 0, primitive: 0, light: None });
                     }
                 };
             }
@@ -52,4 +65,26 @@ impl Hittable for Sphere {
 
         None
     }
+
+    fn occluded(&self, r: &Ray, t_range: Range<f32>) -> bool {
+        count_intersection_test();
+        let oc = r.origin - self.center;
+        let a = Vec3::dot(&r.direction, &r.direction);
+        let b = 2.0 * Vec3::dot(&oc, &r.direction);
+        let c = Vec3::dot(&oc, &oc) - self.radius.powf(2.);
+        let discriminant = b.powf(2.) - 4. * a * c;
+        if discriminant <= 0.0 {
+            return false;
+        }
+
+        let sqrt = discriminant.sqrt();
+        t_range.contains(&((-b - sqrt) / (2.0 * a))) || t_range.contains(&((-b + sqrt) / (2.0 * a)))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        // radius may be negative (e.g: for hollow dielectric spheres)
+        let r = self.radius.abs();
+        let r = Vec3::new(r, r, r);
+        Some(Aabb::new(self.center - r, self.center + r))
+    }
 }
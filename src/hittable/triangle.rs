@@ -0,0 +1,217 @@
+use std::ops::Range;
+
+use crate::counters::count_intersection_test;
+use crate::material::MaterialT;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+use super::{Aabb, HitRecord, Hittable, HittableT};
+
+/// A single triangle.
+///
+/// The triangle's normal faces the side from which its vertices appear in
+/// counter-clockwise order.
+#[derive(Debug)]
+pub struct Triangle {
+    pub v0: Vec3,
+    pub v1: Vec3,
+    pub v2: Vec3,
+    pub material: MaterialT,
+}
+
+impl Triangle {
+    /// Create a new triangle with vertices `v0`, `v1`, and `v2`
+    pub fn new_hittable(v0: Vec3, v1: Vec3, v2: Vec3, material: MaterialT) -> HittableT {
+        Triangle {
+            v0,
+            v1,
+            v2,
+            material,
+        }
+        .into()
+    }
+}
+
+/// Möller–Trumbore ray-triangle intersection.
+/// Returns the `t` along the ray, and the barycentric `(u, v)` coordinates of
+/// the hit point (corresponding to the weights of `v1` and `v2` respectively).
+#[inline]
+pub(crate) fn intersect(
+    r: &Ray,
+    t_range: &Range<f32>,
+    v0: &Vec3,
+    v1: &Vec3,
+    v2: &Vec3,
+) -> Option<(f32, f32, f32)> {
+    let e1 = *v1 - *v0;
+    let e2 = *v2 - *v0;
+
+    let pvec = r.direction.cross(&e2);
+    let det = e1.dot(&pvec);
+    // ray is parallel to the triangle (or the triangle is degenerate). `det`
+    // scales with the edges' (and direction's) lengths, so the cutoff does
+    // too, letting tiny triangles be hit (compared squared, to avoid roots)
+    let scale = e1.squared_length() * e2.squared_length() * r.direction.squared_length();
+    if det * det <= f32::EPSILON * f32::EPSILON * scale {
+        return None;
+    }
+    let inv_det = 1. / det;
+
+    let tvec = r.origin - *v0;
+    let u = tvec.dot(&pvec) * inv_det;
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+
+    let qvec = tvec.cross(&e1);
+    let v = r.direction.dot(&qvec) * inv_det;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+
+    let t = e2.dot(&qvec) * inv_det;
+    if t_range.contains(&t) {
+        Some((t, u, v))
+    } else {
+        None
+    }
+}
+
+/// Bounding box of a triangle
+#[inline]
+pub(super) fn bounds(v0: &Vec3, v1: &Vec3, v2: &Vec3) -> Aabb {
+    Aabb::new(v0.min(v1).min(v2), v0.max(v1).max(v2))
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord<'_>> {
+        count_intersection_test();
+        let (t, u, v) = intersect(r, &t_range, &self.v0, &self.v1, &self.v2)?;
+        Some(HitRecord {
+            t,
+            p: r.point_at_param(t),
+            normal: (self.v1 - self.v0).cross(&(self.v2 - self.v0)).normalize(),
+            // (barycentric coordinates)
+            uv: (u, v),
+            material: &self.material,
+            object: 0,
+            primitive: 0,
+            light: None,
+        })
+    }
+
+    fn occluded(&self, r: &Ray, t_range: Range<f32>) -> bool {
+        count_intersection_test();
+        intersect(r, &t_range, &self.v0, &self.v1, &self.v2).is_some()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(bounds(&self.v0, &self.v1, &self.v2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T_RANGE: Range<f32> = 0.001..f32::MAX;
+
+    fn v0() -> Vec3 {
+        Vec3::new(0., 0., 0.)
+    }
+    fn v1() -> Vec3 {
+        Vec3::new(1., 0., 0.)
+    }
+    fn v2() -> Vec3 {
+        Vec3::new(0., 1., 0.)
+    }
+
+    /// Intersect the unit right triangle with a ray pointing down -z, from
+    /// above `(x, y)`
+    fn hit_at(x: f32, y: f32) -> Option<(f32, f32, f32)> {
+        let r = Ray::new(Vec3::new(x, y, 2.), Vec3::new(0., 0., -1.));
+        intersect(&r, &T_RANGE, &v0(), &v1(), &v2())
+    }
+
+    #[test]
+    fn hit_returns_distance_and_barycentrics() {
+        let (t, u, v) = hit_at(0.25, 0.5).unwrap();
+        assert!((t - 2.).abs() < 1e-6);
+        assert!((u - 0.25).abs() < 1e-6);
+        assert!((v - 0.5).abs() < 1e-6);
+
+        // both sides of the triangle are hit
+        let r = Ray::new(Vec3::new(0.25, 0.25, -3.), Vec3::new(0., 0., 2.));
+        let (t, _, _) = intersect(&r, &T_RANGE, &v0(), &v1(), &v2()).unwrap();
+        assert!((t - 1.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn misses() {
+        // outside each of the edges
+        assert!(hit_at(-0.1, 0.5).is_none());
+        assert!(hit_at(0.5, -0.1).is_none());
+        assert!(hit_at(0.6, 0.6).is_none());
+
+        // parallel to the triangle's plane
+        let r = Ray::new(Vec3::new(-1., 0.25, 0.), Vec3::new(1., 0., 0.));
+        assert!(intersect(&r, &T_RANGE, &v0(), &v1(), &v2()).is_none());
+
+        // behind the ray, and past the end of the range
+        let r = Ray::new(Vec3::new(0.25, 0.25, -1.), Vec3::new(0., 0., -1.));
+        assert!(intersect(&r, &T_RANGE, &v0(), &v1(), &v2()).is_none());
+        let r = Ray::new(Vec3::new(0.25, 0.25, 2.), Vec3::new(0., 0., -1.));
+        assert!(intersect(&r, &(0.001..1.), &v0(), &v1(), &v2()).is_none());
+    }
+
+    #[test]
+    fn edges_and_vertices_are_hit() {
+        for &(x, y) in &[
+            (0., 0.),
+            (1., 0.),
+            (0., 1.),
+            (0.5, 0.),
+            (0., 0.5),
+            (0.5, 0.5),
+        ] {
+            assert!(hit_at(x, y).is_some(), "({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn tiny_triangles_are_hit() {
+        for &size in &[1e-2, 1e-4, 1e-5] {
+            let (v0, v1, v2) = (v0() * size, v1() * size, v2() * size);
+            let r = Ray::new(Vec3::new(0.25, 0.25, 2.) * size, Vec3::new(0., 0., -1.));
+            let (t, u, v) = intersect(&r, &(0.0..f32::MAX), &v0, &v1, &v2)
+                .unwrap_or_else(|| panic!("triangle of size {} was missed", size));
+            assert!((t / size - 2.).abs() < 1e-4);
+            assert!((u - 0.25).abs() < 1e-4 && (v - 0.25).abs() < 1e-4);
+
+            // (but rays parallel to them still miss)
+            let r = Ray::new(Vec3::new(-1., 0.25, 0.) * size, Vec3::new(1., 0., 0.));
+            assert!(intersect(&r, &(0.0..f32::MAX), &v0, &v1, &v2).is_none());
+        }
+    }
+
+    #[test]
+    fn degenerate_triangles_are_never_hit() {
+        let r = Ray::new(Vec3::new(0.5, 0., 2.), Vec3::new(0., 0., -1.));
+        assert!(intersect(&r, &T_RANGE, &v0(), &v1(), &v1()).is_none());
+        assert!(intersect(&r, &T_RANGE, &v0(), &v1(), &(v1() * 2.)).is_none());
+    }
+
+    #[test]
+    fn normal_follows_winding_order() {
+        let material = crate::material::Lambertian::new_material(Vec3::new(0.5, 0.5, 0.5));
+        let tri = Triangle::new_hittable(v0(), v1(), v2(), material);
+        let r = Ray::new(Vec3::new(0.25, 0.25, 2.), Vec3::new(0., 0., -1.));
+        let rec = tri.hit(&r, T_RANGE).unwrap();
+        assert!((rec.normal.z - 1.).abs() < 1e-6);
+        assert_eq!(rec.uv, (0.25, 0.25));
+
+        let bounds = tri.bounding_box().unwrap();
+        assert_eq!([bounds.min.x, bounds.min.y, bounds.min.z], [0., 0., 0.]);
+        assert_eq!([bounds.max.x, bounds.max.y, bounds.max.z], [1., 1., 0.]);
+    }
+}
@@ -0,0 +1,73 @@
+use std::f32::consts::PI;
+
+use crate::util::{luminance, uniform_cone};
+use crate::vec3::Vec3;
+
+use super::{Light, LightSample, LightT};
+
+/// An infinitely far away light (e.g: the sun), lighting the whole scene from
+/// the same `direction`.
+///
+/// Lights with a nonzero angular diameter are seen as a small disk in the
+/// sky, and cast soft shadows. Either way, `irradiance` is the light arriving
+/// at surfaces facing the light.
+#[derive(Debug)]
+pub struct DirectionalLight {
+    pub direction: Vec3,
+    pub irradiance: Vec3,
+    /// `1 - cos` of the half-angle of the disk's cone (0 for a single
+    /// direction)
+    pub width: f32,
+}
+
+impl DirectionalLight {
+    /// Create a new directional light, seen along `direction` (i.e: the
+    /// direction pointing _towards_ the light), as a disk of
+    /// `angular_diameter` (in degrees)
+    pub fn new_light(direction: Vec3, irradiance: Vec3, angular_diameter: f32) -> LightT {
+        let half_angle = (angular_diameter / 2.).to_radians();
+        // 1 - cos(x), without cancellation
+        let width = 2. * (half_angle / 2.).sin().powi(2);
+        DirectionalLight {
+            direction: direction.normalize(),
+            irradiance,
+            width,
+        }
+        .into()
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _origin: &Vec3, u: (f32, f32)) -> Option<LightSample> {
+        if self.width <= 0. {
+            return Some(LightSample {
+                dir: self.direction,
+                dist: f32::MAX,
+                radiance: self.irradiance,
+                pdf: 1.,
+            });
+        }
+
+        // spread the irradiance across the disk
+        let solid_angle = 2. * PI * self.width;
+        Some(LightSample {
+            dir: uniform_cone(&self.direction, self.width, u),
+            dist: f32::MAX,
+            radiance: self.irradiance / solid_angle,
+            pdf: 1. / solid_angle,
+        })
+    }
+
+    fn pdf(&self, _origin: &Vec3, _dir: &Vec3) -> f32 {
+        0.
+    }
+
+    fn power(&self, scene_radius: f32) -> f32 {
+        // (the light falling onto a disk covering the scene)
+        PI * scene_radius * scene_radius * luminance(self.irradiance)
+    }
+
+    fn hittable(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,311 @@
+//! Light-emitting shapes, which can be sampled directly (i.e: "next event
+//! estimation"), instead of waiting for paths to stumble upon them.
+//!
+//! Besides the lights matching the world's emissive objects, scenes may also
+//! have punctual lights (i.e: infinitely small or infinitely far away), which
+//! aren't part of the world, and can _only_ be sampled directly.
+
+use std::f32::consts::PI;
+
+use crate::util::luminance;
+use crate::vec3::Vec3;
+
+mod directional;
+mod point;
+mod quad;
+mod sphere;
+mod spot;
+mod triangle;
+
+pub use directional::DirectionalLight;
+pub use point::PointLight;
+pub use quad::QuadLight;
+pub use sphere::SphereLight;
+pub use spot::SpotLight;
+pub use triangle::TriangleLight;
+
+/// A point sampled on a light, as seen from the point being lit
+#[derive(Debug, Clone, Copy)]
+pub struct LightSample {
+    /// (unit) direction towards the sampled point
+    pub dir: Vec3,
+    /// distance to the sampled point
+    pub dist: f32,
+    /// radiance emitted from the sampled point, towards the point being lit
+    pub radiance: Vec3,
+    /// probability density of having sampled `dir` (with respect to solid
+    /// angle)
+    ///
+    /// Lights seen along a single direction (e.g: point lights) have a pdf of
+    /// 1, with `radiance` holding the irradiance arriving from them instead.
+    pub pdf: f32,
+}
+
+/// A light source that can be sampled directly
+pub trait Light: Send + Sync {
+    /// Sample a point on the light, as seen from `origin`, using the point `u`
+    /// in the unit square. Returns None if no point could be sampled.
+    fn sample(&self, origin: &Vec3, u: (f32, f32)) -> Option<LightSample>;
+
+    /// Returns the probability density (with respect to solid angle) of
+    /// [Light::sample] picking the (unit) direction `dir` from `origin`, or 0
+    /// if the light can't be seen in that direction.
+    fn pdf(&self, origin: &Vec3, dir: &Vec3) -> f32;
+
+    /// Returns the (approximate) total power emitted by the light, which sets
+    /// how often it's sampled relative to the scene's other lights.
+    /// `scene_radius` bounds the part of the world lit by infinitely far away
+    /// lights.
+    fn power(&self, scene_radius: f32) -> f32;
+
+    /// Whether the light is part of the world, and can therefore also be hit
+    /// by rays scattered off of surfaces. Punctual lights can't be, so their
+    /// [Light::pdf] is always 0.
+    fn hittable(&self) -> bool {
+        true
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "enum_dispatch")] {
+        pub type LightT = Lights;
+    } else {
+        pub type LightT = Box<dyn Light>;
+    }
+}
+
+macro_rules! impl_ref {
+    ($type:ty) => {
+        impl Light for $type {
+            fn sample(&self, origin: &Vec3, u: (f32, f32)) -> Option<LightSample> {
+                (**self).sample(origin, u)
+            }
+
+            fn pdf(&self, origin: &Vec3, dir: &Vec3) -> f32 {
+                (**self).pdf(origin, dir)
+            }
+
+            fn power(&self, scene_radius: f32) -> f32 {
+                (**self).power(scene_radius)
+            }
+
+            fn hittable(&self) -> bool {
+                (**self).hittable()
+            }
+        }
+    };
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "enum_dispatch")] {
+        // When using enum dispatch:
+        // - create the enum
+        // - Implements Light for &enum and &mut enum
+        // - Implements From<variant> for enum
+        // - Implements enum dispatch by implementing Light on the enum
+        macro_rules! lights {
+            (
+                $(#[$meta:meta])*
+                $(pub)? enum $enum_name:ident {
+                    $($light_name:ident($light_type:ty),)*
+                }
+            ) => {
+                $(#[$meta])*
+                pub enum $enum_name {
+                    $($light_name($light_type),)*
+                }
+
+                impl_ref!(&$enum_name);
+                impl_ref!(&mut $enum_name);
+
+                $(
+                    impl From<$light_type> for $enum_name {
+                        fn from(x: $light_type) -> $enum_name {
+                            $enum_name::$light_name(x)
+                        }
+                    }
+                )*
+
+                impl Light for $enum_name {
+                    fn sample(&self, origin: &Vec3, u: (f32, f32)) -> Option<LightSample> {
+                        use self::$enum_name::*;
+                        match self {
+                            $($light_name(x) => x.sample(origin, u),)*
+                        }
+                    }
+
+                    fn pdf(&self, origin: &Vec3, dir: &Vec3) -> f32 {
+                        use self::$enum_name::*;
+                        match self {
+                            $($light_name(x) => x.pdf(origin, dir),)*
+                        }
+                    }
+
+                    fn power(&self, scene_radius: f32) -> f32 {
+                        use self::$enum_name::*;
+                        match self {
+                            $($light_name(x) => x.power(scene_radius),)*
+                        }
+                    }
+
+                    fn hittable(&self) -> bool {
+                        use self::$enum_name::*;
+                        match self {
+                            $($light_name(x) => x.hittable(),)*
+                        }
+                    }
+                }
+            };
+        }
+    } else {
+        // When using dynamic dispatch:
+        // - Implement Light for various Box<dyn>, &dyn, and &mut dyn Light
+        // - Implement From<Light> for Box<dyn Light> for each Light type
+        macro_rules! lights {
+            (
+                $(#[$meta:meta])*
+                $(pub)? enum $enum_name:ident {
+                    $($light_name:ident($light_type:ty),)*
+                }
+            ) => {
+                impl_ref!(Box<dyn Light>);
+                impl_ref!(&dyn Light);
+                impl_ref!(&mut dyn Light);
+
+                $(
+                    impl From<$light_type> for Box<dyn Light> {
+                        fn from(x: $light_type) -> Box<dyn Light> {
+                            Box::new(x)
+                        }
+                    }
+                )*
+            };
+        }
+    }
+}
+
+lights! {
+    #[derive(Debug)]
+    pub enum Lights {
+        Directional(DirectionalLight),
+        Point(PointLight),
+        Quad(QuadLight),
+        Sphere(SphereLight),
+        Spot(SpotLight),
+        Triangle(TriangleLight),
+    }
+}
+
+/// A scene's lights, along with the distribution they're picked from when
+/// sampling them directly.
+///
+/// Lights are picked in proportion to their power, so a single large light
+/// isn't sampled as rarely as each of the (many) tiny faces of a tessellated
+/// one.
+pub struct LightSet {
+    lights: Vec<LightT>,
+    /// probability of picking each light
+    probs: Vec<f32>,
+    /// running sum of `probs`
+    cdf: Vec<f32>,
+}
+
+impl LightSet {
+    /// A scene without any lights
+    pub const fn empty() -> LightSet {
+        LightSet {
+            lights: Vec::new(),
+            probs: Vec::new(),
+            cdf: Vec::new(),
+        }
+    }
+
+    /// Create a set of `lights`, lighting a world that fits in a sphere of
+    /// `scene_radius`
+    pub fn new(lights: Vec<LightT>, scene_radius: f32) -> LightSet {
+        let mut weights: Vec<f32> = lights
+            .iter()
+            .map(|l| l.power(scene_radius))
+            .map(|p| if p.is_finite() { p.max(0.) } else { 0. })
+            .collect();
+        let mut total: f32 = weights.iter().sum();
+        if total <= 0. {
+            // (e.g: all the lights are black) fall back to picking uniformly
+            weights.iter_mut().for_each(|w| *w = 1.);
+            total = weights.len() as f32;
+        }
+
+        let probs: Vec<f32> = weights.iter().map(|w| w / total).collect();
+        let cdf = probs
+            .iter()
+            .scan(0., |sum, p| {
+                *sum += p;
+                Some(*sum)
+            })
+            .collect();
+
+        LightSet { lights, probs, cdf }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    /// Pick a light using `u` (in [0, 1)). Returns the light, along with the
+    /// probability of having picked it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set is empty.
+    pub fn pick(&self, u: f32) -> (&LightT, f32) {
+        let i = self.cdf.partition_point(|&c| c <= u);
+        // the cdf may fall short of 1 (due to rounding), in which case the
+        // last light that can be picked at all is picked
+        let i = self.probs[..=i.min(self.probs.len() - 1)]
+            .iter()
+            .rposition(|&p| p > 0.)
+            .expect("some light has a non-zero probability");
+        (&self.lights[i], self.probs[i])
+    }
+
+    /// Returns the probability density (with respect to solid angle) of
+    /// picking the (unit) direction `dir` from `origin` by sampling light
+    /// number `index` (accounting for the probability of picking it)
+    pub fn pdf(&self, index: usize, origin: &Vec3, dir: &Vec3) -> f32 {
+        self.probs[index] * self.lights[index].pdf(origin, dir)
+    }
+}
+
+/// Power emitted by a surface of `area`, emitting `emit` radiance (from one
+/// side)
+fn area_power(area: f32, emit: Vec3) -> f32 {
+    PI * area * luminance(emit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(intensity: f32) -> LightT {
+        PointLight::new_light(Vec3::new(0., 0., 0.), Vec3::new(1., 1., 1.) * intensity)
+    }
+
+    #[test]
+    fn lights_are_picked_in_proportion_to_power() {
+        let lights = LightSet::new(vec![point(1.), point(0.), point(3.)], 1.);
+        let prob = |u| lights.pick(u).1;
+        assert!((prob(0.1) - 0.25).abs() < 1e-6);
+        assert!((prob(0.5) - 0.75).abs() < 1e-6);
+        // lights without any power are never picked, even when the cdf
+        // falls short of 1
+        assert!((prob(0.999_999_9) - 0.75).abs() < 1e-6);
+        assert_eq!(lights.probs[1], 0.);
+    }
+
+    #[test]
+    fn black_lights_are_picked_uniformly() {
+        let lights = LightSet::new(vec![point(0.), point(0.)], 1.);
+        assert_eq!(lights.pick(0.25).1, 0.5);
+        assert_eq!(lights.pick(0.75).1, 0.5);
+    }
+}
@@ -0,0 +1,55 @@
+use std::f32::consts::PI;
+
+use crate::util::luminance;
+use crate::vec3::Vec3;
+
+use super::{Light, LightSample, LightT};
+
+/// An infinitely small light, emitting the same intensity in every direction
+/// (and falling off with the square of the distance from it).
+#[derive(Debug)]
+pub struct PointLight {
+    pub position: Vec3,
+    pub intensity: Vec3,
+}
+
+impl PointLight {
+    /// Create a new point light at `position`, emitting `intensity` radiant
+    /// intensity
+    pub fn new_light(position: Vec3, intensity: Vec3) -> LightT {
+        PointLight {
+            position,
+            intensity,
+        }
+        .into()
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, origin: &Vec3, _u: (f32, f32)) -> Option<LightSample> {
+        let dist2 = (self.position - *origin).squared_length();
+        if dist2 == 0. {
+            return None;
+        }
+        let dist = dist2.sqrt();
+
+        Some(LightSample {
+            dir: (self.position - *origin) / dist,
+            dist,
+            radiance: self.intensity / dist2,
+            pdf: 1.,
+        })
+    }
+
+    fn pdf(&self, _origin: &Vec3, _dir: &Vec3) -> f32 {
+        0.
+    }
+
+    fn power(&self, _scene_radius: f32) -> f32 {
+        4. * PI * luminance(self.intensity)
+    }
+
+    fn hittable(&self) -> bool {
+        false
+    }
+}
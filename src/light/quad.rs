@@ -0,0 +1,67 @@
+use crate::hittable::intersect_quad;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+use super::{area_power, Light, LightSample, LightT};
+
+/// A parallelogram light (spanned by edges `u` and `v` from corner `q`),
+/// emitting the same radiance from both of its sides.
+///
+/// Samples are spread uniformly across the light's area.
+#[derive(Debug)]
+pub struct QuadLight {
+    pub q: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub emit: Vec3,
+}
+
+impl QuadLight {
+    /// Create a new quad light with corner `q`, and edges `u` and `v`,
+    /// emitting `emit` radiance
+    pub fn new_light(q: Vec3, u: Vec3, v: Vec3, emit: Vec3) -> LightT {
+        QuadLight { q, u, v, emit }.into()
+    }
+
+    /// Converts the (uniform) area pdf of a point `dist` away along (unit)
+    /// direction `dir` into a pdf with respect to solid angle
+    fn solid_angle_pdf(&self, dir: &Vec3, dist: f32) -> f32 {
+        let n = self.u.cross(&self.v);
+        let area = n.length();
+        let cos = (n.dot(dir) / area).abs();
+        dist * dist / (cos * area)
+    }
+}
+
+impl Light for QuadLight {
+    fn sample(&self, origin: &Vec3, u: (f32, f32)) -> Option<LightSample> {
+        let p = self.q + u.0 * self.u + u.1 * self.v;
+        let dist = (p - *origin).length();
+        let dir = (p - *origin) / dist;
+
+        let pdf = self.solid_angle_pdf(&dir, dist);
+        // the light is seen edge-on (or the origin lies on it)
+        if !pdf.is_finite() {
+            return None;
+        }
+
+        Some(LightSample {
+            dir,
+            dist,
+            radiance: self.emit,
+            pdf,
+        })
+    }
+
+    fn pdf(&self, origin: &Vec3, dir: &Vec3) -> f32 {
+        let r = Ray::new(*origin, *dir);
+        match intersect_quad(&r, &(0.0..f32::MAX), &self.q, &self.u, &self.v) {
+            Some((dist, _, _)) => self.solid_angle_pdf(dir, dist),
+            None => 0.,
+        }
+    }
+
+    fn power(&self, _scene_radius: f32) -> f32 {
+        area_power(self.u.cross(&self.v).length(), self.emit)
+    }
+}
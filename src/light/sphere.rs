@@ -0,0 +1,119 @@
+use std::f32::consts::PI;
+
+use crate::util::{uniform_cone, uniform_sphere};
+use crate::vec3::Vec3;
+
+use super::{area_power, Light, LightSample, LightT};
+
+/// A spherical light, emitting the same radiance from every point on its
+/// surface.
+///
+/// Lit points outside the sphere sample the cone of directions subtended by
+/// it (so every sample lands on the visible side of the sphere). Points
+/// inside the sphere fall back to sampling its surface area.
+#[derive(Debug)]
+pub struct SphereLight {
+    pub center: Vec3,
+    pub radius: f32,
+    pub emit: Vec3,
+}
+
+impl SphereLight {
+    /// Create a new sphere light with a specified `center` and `radius`,
+    /// emitting `emit` radiance
+    pub fn new_light(center: Vec3, radius: f32, emit: Vec3) -> LightT {
+        SphereLight {
+            center,
+            // radius may be negative (e.g: for hollow spheres)
+            radius: radius.abs(),
+            emit,
+        }
+        .into()
+    }
+
+    /// Returns `1 - cos(theta_max)` for the cone of directions subtended by
+    /// the sphere, as seen from a point `dist2` (squared) away from its
+    /// center. Computed without cancellation, for far away spheres.
+    fn cone_width(&self, dist2: f32) -> f32 {
+        let sin2_max = self.radius * self.radius / dist2;
+        let cos_max = (1. - sin2_max).max(0.).sqrt();
+        sin2_max / (1. + cos_max)
+    }
+
+    /// Returns the distance along (unit) direction `dir` from `origin` to the
+    /// far side of the sphere (for origins inside the sphere)
+    fn exit_dist(&self, origin: &Vec3, dir: &Vec3) -> f32 {
+        let oc = *origin - self.center;
+        let b = oc.dot(dir);
+        let c = oc.dot(&oc) - self.radius * self.radius;
+        -b + (b * b - c).max(0.).sqrt()
+    }
+
+    /// Surface area pdf, converted to solid angle, for origins inside the
+    /// sphere
+    fn area_pdf(&self, origin: &Vec3, dir: &Vec3, dist: f32) -> f32 {
+        let p = *origin + dist * *dir;
+        let cos = ((p - self.center) / self.radius).dot(dir).abs();
+        let area = 4. * PI * self.radius * self.radius;
+        dist * dist / (cos * area)
+    }
+}
+
+impl Light for SphereLight {
+    fn sample(&self, origin: &Vec3, u: (f32, f32)) -> Option<LightSample> {
+        let oc = self.center - *origin;
+        let dist2 = oc.squared_length();
+
+        if dist2 <= self.radius * self.radius {
+            let p = self.center + self.radius * uniform_sphere(u);
+            let dist = (p - *origin).length();
+            if dist == 0. {
+                return None;
+            }
+            let dir = (p - *origin) / dist;
+            return Some(LightSample {
+                dir,
+                dist,
+                radiance: self.emit,
+                pdf: self.area_pdf(origin, &dir, dist),
+            });
+        }
+
+        // uniformly sample a direction within the cone subtended by the sphere
+        let width = self.cone_width(dist2);
+        let dir = uniform_cone(&(oc / dist2.sqrt()), width, u);
+
+        // distance to the near side of the sphere
+        let perp2 = oc.cross(&dir).squared_length();
+        let dist = oc.dot(&dir) - (self.radius * self.radius - perp2).max(0.).sqrt();
+
+        Some(LightSample {
+            dir,
+            dist,
+            radiance: self.emit,
+            pdf: 1. / (2. * PI * width),
+        })
+    }
+
+    fn pdf(&self, origin: &Vec3, dir: &Vec3) -> f32 {
+        let oc = self.center - *origin;
+        let dist2 = oc.squared_length();
+
+        if dist2 <= self.radius * self.radius {
+            let dist = self.exit_dist(origin, dir);
+            return self.area_pdf(origin, dir, dist);
+        }
+
+        let width = self.cone_width(dist2);
+        let cos_theta = oc.dot(dir) / dist2.sqrt();
+        if 1. - cos_theta <= width {
+            1. / (2. * PI * width)
+        } else {
+            0.
+        }
+    }
+
+    fn power(&self, _scene_radius: f32) -> f32 {
+        area_power(4. * PI * self.radius * self.radius, self.emit)
+    }
+}
@@ -0,0 +1,90 @@
+use std::f32::consts::PI;
+
+use crate::util::luminance;
+use crate::vec3::Vec3;
+
+use super::{Light, LightSample, LightT};
+
+/// A point light which only shines within a cone around its `direction`.
+///
+/// Its intensity is constant within `cos_inner` (the cosine of the inner
+/// cone's half-angle), and smoothly falls off to nothing at `cos_outer`.
+#[derive(Debug)]
+pub struct SpotLight {
+    pub position: Vec3,
+    pub direction: Vec3,
+    pub intensity: Vec3,
+    pub cos_inner: f32,
+    pub cos_outer: f32,
+}
+
+impl SpotLight {
+    /// Create a new spot light at `position`, pointing along `direction`, and
+    /// emitting `intensity` radiant intensity. The cone's edge softens from
+    /// its `inner_angle` to its `outer_angle` (both half-angles, in degrees).
+    pub fn new_light(
+        position: Vec3,
+        direction: Vec3,
+        intensity: Vec3,
+        inner_angle: f32,
+        outer_angle: f32,
+    ) -> LightT {
+        let outer_angle = outer_angle.max(inner_angle);
+        SpotLight {
+            position,
+            direction: direction.normalize(),
+            intensity,
+            cos_inner: inner_angle.to_radians().cos(),
+            cos_outer: outer_angle.to_radians().cos(),
+        }
+        .into()
+    }
+
+    /// Fraction of the light's intensity emitted along (unit) direction `dir`
+    fn falloff(&self, dir: &Vec3) -> f32 {
+        let cos = self.direction.dot(dir);
+        if cos >= self.cos_inner {
+            return 1.;
+        }
+        let t = ((cos - self.cos_outer) / (self.cos_inner - self.cos_outer)).clamp(0., 1.);
+        // smoothstep
+        t * t * (3. - 2. * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, origin: &Vec3, _u: (f32, f32)) -> Option<LightSample> {
+        let dist2 = (self.position - *origin).squared_length();
+        if dist2 == 0. {
+            return None;
+        }
+        let dist = dist2.sqrt();
+        let dir = (self.position - *origin) / dist;
+
+        let falloff = self.falloff(&-dir);
+        if falloff == 0. {
+            return None;
+        }
+
+        Some(LightSample {
+            dir,
+            dist,
+            radiance: self.intensity * (falloff / dist2),
+            pdf: 1.,
+        })
+    }
+
+    fn pdf(&self, _origin: &Vec3, _dir: &Vec3) -> f32 {
+        0.
+    }
+
+    fn power(&self, _scene_radius: f32) -> f32 {
+        // (approximating the falloff as linear in the cosine)
+        let cos = 0.5 * (self.cos_inner + self.cos_outer);
+        2. * PI * (1. - cos) * luminance(self.intensity)
+    }
+
+    fn hittable(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,71 @@
+use crate::hittable::intersect_triangle;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+use super::{area_power, Light, LightSample, LightT};
+
+/// A triangular light, emitting the same radiance from both of its sides.
+///
+/// Samples are spread uniformly across the light's area.
+#[derive(Debug)]
+pub struct TriangleLight {
+    pub v0: Vec3,
+    pub v1: Vec3,
+    pub v2: Vec3,
+    pub emit: Vec3,
+}
+
+impl TriangleLight {
+    /// Create a new triangle light with vertices `v0`, `v1`, and `v2`,
+    /// emitting `emit` radiance
+    pub fn new_light(v0: Vec3, v1: Vec3, v2: Vec3, emit: Vec3) -> LightT {
+        TriangleLight { v0, v1, v2, emit }.into()
+    }
+
+    /// Converts the (uniform) area pdf of a point `dist` away along (unit)
+    /// direction `dir` into a pdf with respect to solid angle
+    fn solid_angle_pdf(&self, dir: &Vec3, dist: f32) -> f32 {
+        let n = (self.v1 - self.v0).cross(&(self.v2 - self.v0));
+        let len = n.length();
+        let cos = (n.dot(dir) / len).abs();
+        dist * dist / (cos * len / 2.)
+    }
+}
+
+impl Light for TriangleLight {
+    fn sample(&self, origin: &Vec3, u: (f32, f32)) -> Option<LightSample> {
+        // uniform barycentric coordinates
+        let s = u.0.sqrt();
+        let (b1, b2) = (s * (1. - u.1), s * u.1);
+        let p = self.v0 + b1 * (self.v1 - self.v0) + b2 * (self.v2 - self.v0);
+
+        let dist = (p - *origin).length();
+        let dir = (p - *origin) / dist;
+
+        let pdf = self.solid_angle_pdf(&dir, dist);
+        // the light is seen edge-on (or the origin lies on it)
+        if !pdf.is_finite() {
+            return None;
+        }
+
+        Some(LightSample {
+            dir,
+            dist,
+            radiance: self.emit,
+            pdf,
+        })
+    }
+
+    fn pdf(&self, origin: &Vec3, dir: &Vec3) -> f32 {
+        let r = Ray::new(*origin, *dir);
+        match intersect_triangle(&r, &(0.0..f32::MAX), &self.v0, &self.v1, &self.v2) {
+            Some((dist, _, _)) => self.solid_angle_pdf(dir, dist),
+            None => 0.,
+        }
+    }
+
+    fn power(&self, _scene_radius: f32) -> f32 {
+        let area = 0.5 * (self.v1 - self.v0).cross(&(self.v2 - self.v0)).length();
+        area_power(area, self.emit)
+    }
+}
@@ -1,25 +1,37 @@
 #![allow(clippy::many_single_char_names)] // lots of math uses single char names
 
+use std::error::Error;
+use std::io;
+use std::path::Path;
 use std::sync::{Arc, RwLock};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use minifb::{Key, Window, WindowOptions};
+use structopt::StructOpt;
 
 pub mod camera;
+pub mod cli;
+pub mod counters;
+pub mod environment;
+pub mod hdr;
 pub mod hittable;
+pub mod light;
 pub mod material;
+pub mod obj;
+pub mod output;
 pub mod ray;
 pub mod render;
+pub mod sampler;
 pub mod scenes;
+pub mod texture;
 pub mod util;
 pub mod vec3;
 
 use camera::{Camera, CameraOpts};
+use cli::{Cli, Command, SceneKind};
+use render::{Accumulator, Aov, AovBuffers, DisplayOpts, RenderError, RenderOpts, Renderer};
 use scenes::Scene;
-
-const BASE_WIDTH: usize = 256;
-const BASE_HEIGHT: usize = 128;
-const DEFAULT_SAMPLES: usize = 4; // speedy, but grainy
+use vec3::Vec3;
 
 const TITLE: &str = "vrai-tracer";
 
@@ -31,34 +43,259 @@ struct Opts {
     cam: CameraOpts,
 }
 
-fn main() -> Result<(), minifb::Error> {
+fn main() {
+    let cmd = Cli::from_args().command();
+
     #[cfg(feature = "enum_dispatch")]
     println!("using enum dispatch");
 
     #[cfg(not(feature = "enum_dispatch"))]
     println!("using dynamic dispatch");
 
-    let args: Vec<String> = std::env::args().collect();
-    let samples = match args.get(1) {
-        Some(s) => s.parse().expect("bad number of samples"),
-        None => DEFAULT_SAMPLES,
+    if let Err(e) = run_command(cmd) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Setup the world, and execute the subcommand on it
+fn run_command(cmd: Command) -> Result<(), Box<dyn Error>> {
+    let args = cmd.args();
+    match args.scene.clone() {
+        SceneKind::Random => match args.seed {
+            Some(seed) => run(scenes::Random::from_seed(seed), cmd),
+            None => run(scenes::Random::new(), cmd),
+        },
+        SceneKind::Chapter => run(scenes::Chapter::new(), cmd),
+        SceneKind::File(path) => run(scenes::FileScene::load(path)?, cmd),
+    }
+}
+
+/// Execute the subcommand on a particular scene
+fn run(scene: impl Scene + 'static, cmd: Command) -> Result<(), Box<dyn Error>> {
+    match cmd {
+        Command::Preview { args } => {
+            preview(scene, args.render_opts(), args.display_opts(), args.denoise)?
+        }
+        Command::Render {
+            args,
+            output,
+            aovs,
+            heatmap,
+        } => render_headless(
+            scene,
+            args.render_opts(),
+            args.display_opts(),
+            args.denoise,
+            &output,
+            &aovs,
+            heatmap.as_deref(),
+        )?,
+        Command::Bench { args, frames } => bench(scene, args.render_opts(), frames)?,
+    }
+
+    Ok(())
+}
+
+/// Prepare a scene for headless rendering, fixing up the camera's aspect
+/// ratio to match the output resolution.
+fn headless_scene<S: Scene>(mut scene: S, opts: &RenderOpts) -> Arc<RwLock<S>> {
+    let mut cam = scene.get_camera().opts();
+    cam.aspect = opts.width as f32 / opts.height as f32;
+    scene.enable_freecam(Camera::new(cam));
+
+    Arc::new(RwLock::new(scene))
+}
+
+/// Save an image to `path`: PNGs are produced using `resolve`, while floating
+/// point formats get the (linear) values returned by `linear`.
+fn save_image(
+    path: &Path,
+    format: output::Format,
+    (width, height): (usize, usize),
+    resolve: impl FnOnce(&mut [u32]),
+    linear: impl FnOnce() -> Vec<Vec3>,
+) -> io::Result<()> {
+    match format {
+        output::Format::Png => {
+            let mut buffer: Vec<u32> = vec![0; width * height];
+            resolve(&mut buffer);
+            output::write_png(path, width, height, &buffer)?;
+        }
+        output::Format::Hdr => output::write_hdr(path, width, height, &linear())?,
+        output::Format::Pfm => output::write_pfm(path, width, height, &linear())?,
+        output::Format::Exr => output::write_exr(path, width, height, &linear())?,
+    }
+    println!("wrote {}", path.display());
+    Ok(())
+}
+
+/// Render a single frame without opening a window, writing the result to
+/// `path` (in a format determined by its extension).
+///
+/// When sampling adaptively, passes of `opts.samples` samples are rendered
+/// until every pixel has either converged, or taken `opts.max_samples`
+/// samples.
+///
+/// If `denoise` is set, the image is denoised before being written. Any
+/// requested `aovs` are written alongside it, with the AOV's name
+/// inserted before the extension (e.g: `out.depth.exr`). If a `heatmap` path
+/// is given, a heatmap of the number of samples taken at each pixel is
+/// written there.
+fn render_headless(
+    scene: impl Scene + 'static,
+    opts: RenderOpts,
+    display: DisplayOpts,
+    denoise: bool,
+    path: &Path,
+    aovs: &[Aov],
+    heatmap: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let output_format = |path: &Path| {
+        output::Format::from_path(path).ok_or_else(|| {
+            format!(
+                "unsupported output format: {} (expected a .png, .hdr, .pfm, or .exr file)",
+                path.display()
+            )
+        })
     };
+    let format = output_format(path)?;
+    let heatmap = match heatmap {
+        Some(path) => Some((path, output_format(path)?)),
+        None => None,
+    };
+
+    let scene = headless_scene(scene, &opts);
+    let mut renderer = Renderer::new(&scene, opts.threads);
+    let mut accum = Accumulator::new(opts.width, opts.height);
 
-    let (base_width, base_height) = match args.get(2) {
-        Some(s) => {
-            let res = s
-                .split('x')
-                .map(|s| s.parse::<usize>().expect("bad resolution"))
-                .collect::<Vec<_>>();
-            (res[0], res[1])
+    let start = Instant::now();
+    let mut frame = renderer.render(opts, &mut accum)?;
+    frame.wait_to_buffer(&mut accum)?;
+    let mut passes = 1;
+    if opts.noise_threshold.is_some() {
+        while !accum.stats().iter().all(|s| s.converged(&opts)) {
+            frame = renderer.render(opts, &mut accum)?;
+            frame.wait_to_buffer(&mut accum)?;
+            passes += 1;
         }
-        None => (BASE_WIDTH, BASE_HEIGHT),
+    }
+
+    let samples = if opts.noise_threshold.is_some() {
+        let total: usize = accum.stats().iter().map(|s| s.n).sum();
+        let avg = total as f32 / (opts.width * opts.height).max(1) as f32;
+        format!("{:.1} samples on average ({} passes)", avg, passes)
+    } else {
+        format!("{} samples", opts.samples)
     };
+    println!(
+        "rendered {}x{} @ {} in {:.2?}",
+        opts.width,
+        opts.height,
+        samples,
+        start.elapsed()
+    );
+    if passes > 1 {
+        println!("last pass:");
+    }
+    println!("{}", frame.stats());
+
+    let aov_buffers = if denoise || !aovs.is_empty() {
+        Some(AovBuffers::render(&*scene.read().unwrap(), &opts))
+    } else {
+        None
+    };
+
+    let mut radiance = accum.radiance();
+    if let (true, Some(aov_buffers)) = (denoise, &aov_buffers) {
+        let start = Instant::now();
+        radiance = render::denoise(&radiance, aov_buffers, opts.threads);
+        println!("denoised in {:.2?}", start.elapsed());
+    }
+
+    let size = (opts.width, opts.height);
+    save_image(
+        path,
+        format,
+        size,
+        |buf| display.resolve_to_buffer(radiance.iter().copied(), buf),
+        || radiance.clone(),
+    )?;
 
+    if let Some(aov_buffers) = aov_buffers {
+        for &aov in aovs {
+            let ext = path.extension().unwrap().to_string_lossy();
+            let aov_path = path.with_extension(format!("{}.{}", aov, ext));
+            save_image(
+                &aov_path,
+                format,
+                size,
+                |buf| aov_buffers.resolve_to_buffer(aov, buf),
+                || aov_buffers.raw(aov),
+            )?;
+        }
+    }
+
+    if let Some((heatmap_path, heatmap_format)) = heatmap {
+        save_image(
+            heatmap_path,
+            heatmap_format,
+            size,
+            |buf| accum.resolve_heatmap_to_buffer(buf),
+            || accum.sample_counts(),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Render `frames` frames without opening a window, and report timings.
+fn bench(scene: impl Scene + 'static, opts: RenderOpts, frames: usize) -> Result<(), RenderError> {
+    let scene = headless_scene(scene, &opts);
+    let mut renderer = Renderer::new(&scene, opts.threads);
+    let mut accum = Accumulator::new(opts.width, opts.height);
+
+    let mut times = Vec::new();
+    for i in 0..frames {
+        accum.reset();
+        let start = Instant::now();
+        let mut frame = renderer.render(opts, &mut accum)?;
+        frame.wait_to_buffer(&mut accum)?;
+        let elapsed = start.elapsed();
+        println!(
+            "frame {}: {:.2?} ({:.2} Mrays/s)",
+            i,
+            elapsed,
+            frame.stats().rays_per_sec() / 1e6
+        );
+        times.push(elapsed);
+    }
+
+    let total: Duration = times.iter().sum();
+    println!(
+        "{}x{} @ {} samples on {} threads: avg {:.2?}, min {:.2?}, max {:.2?}",
+        opts.width,
+        opts.height,
+        opts.samples,
+        renderer.threads(),
+        total / frames as u32,
+        times.iter().min().unwrap(),
+        times.iter().max().unwrap(),
+    );
+    Ok(())
+}
+
+/// Open a window with a live preview of the scene
+fn preview(
+    scene: impl Scene + 'static,
+    render_opts: RenderOpts,
+    mut display: DisplayOpts,
+    mut denoise: bool,
+) -> Result<(), Box<dyn Error>> {
     let mut window = Window::new(
         TITLE,
-        base_width,
-        base_height,
+        render_opts.width,
+        render_opts.height,
         WindowOptions {
             // scale: minifb::Scale::X2,
             resize: true,
@@ -66,32 +303,39 @@ fn main() -> Result<(), minifb::Error> {
         },
     )?;
 
-    let mut buffer: Vec<u32> = vec![0; base_width * base_height];
+    let mut buffer: Vec<u32> = vec![0; render_opts.width * render_opts.height];
+    // samples keep accumulating until the camera / render options change
+    let mut accum = Accumulator::new(render_opts.width, render_opts.height);
+    // which AOV is being viewed (if any), along with the (lazily rendered) AOVs
+    let mut view: Option<Aov> = None;
+    let mut aovs: Option<AovBuffers> = None;
+    // whether the samples-per-pixel heatmap is being viewed (over any AOV)
+    let mut heatmap = false;
+    // denoised copy of the accumulator, as of the last completed pass (along
+    // with how many samples it was denoised from)
+    let mut denoised: Option<(Vec<Vec3>, usize)> = None;
 
-    let mut init_time = std::time::Instant::now();
+    let mut init_time = Instant::now();
     let mut last_frame = init_time;
     let mut fups = util::SmoothAvg::new();
 
-    // setup the world
-    let scene = scenes::Random::new();
-    // let scene = scenes::Chapter::new();
-
     // various live-controllable options
     let mut opts = Opts {
         movement: false,
         freeze: false,
-        samples,
+        samples: render_opts.samples,
         cam: scene.get_camera().opts(),
     };
 
     // The main loop.
     //
     // Through the power of t h r e a d i n g, the render thread is not blocked
-    // on the ray tracer thread. Instead, the ray tracer returns a
+    // on the ray tracer threads. Instead, the renderer returns a
     // RenderProgress struct which can be used to continuously flush progress
     // to the framebuffer.
 
     let scene = Arc::new(RwLock::new(scene));
+    let mut renderer = Renderer::new(&scene, render_opts.threads);
     let mut current_frame = render::RenderProgress::default();
 
     while window.is_open() && !window.is_key_down(Key::Escape) {
@@ -100,16 +344,48 @@ fn main() -> Result<(), minifb::Error> {
         // let (width, height) = (width / 2, height / 2); // because scaling
         if buffer.len() != width * height {
             buffer = vec![0; width * height];
+            accum = Accumulator::new(width, height);
+            aovs = None;
+            denoised = None;
             current_frame.invalidate();
         }
+        if width == 0 || height == 0 {
+            // (e.g: the window is minimized) there's nothing to render, but
+            // the window still has to process events
+            window.update();
+            continue;
+        }
 
-        if current_frame.poll_done() && !opts.freeze {
+        let frame_opts = RenderOpts {
+            width,
+            height,
+            samples: opts.samples,
+            ..render_opts
+        };
+
+        if current_frame.poll_done()? && !opts.freeze {
             // kick off another frame!
 
+            // pick up any chunks sent just before the threads finished, which
+            // would otherwise be dropped along with the finished frame
+            current_frame.flush_to_buffer(&mut accum);
+
             // Update frame-rate counter
             fups.update(1000. / last_frame.elapsed().as_millis() as f32);
             last_frame += last_frame.elapsed();
-            window.set_title(format!("{} - {:.2} fups", TITLE, fups.get()).as_str());
+
+            // denoise the samples gathered so far (before the scene changes),
+            // but only once they've settled: denoising (and rendering the
+            // AOVs it relies on) every pass of a moving scene would stall the
+            // preview
+            let denoised_samples = denoised.as_ref().map_or(0, |(_, n)| *n);
+            if denoise && !opts.movement && accum.samples() > denoised_samples {
+                let aovs = aovs.get_or_insert_with(|| {
+                    AovBuffers::render(&*scene.read().unwrap(), &frame_opts)
+                });
+                let image = render::denoise(&accum.radiance(), aovs, frame_opts.threads);
+                denoised = Some((image, accum.samples()));
+            }
 
             // update camera aperture
             opts.cam.aspect = width as f32 / height as f32;
@@ -117,9 +393,13 @@ fn main() -> Result<(), minifb::Error> {
             // perform any scene updates
             {
                 let time = if opts.movement {
+                    // the scene is changing, so old samples are useless
+                    accum.reset();
+                    aovs = None;
+                    denoised = None;
                     init_time.elapsed()
                 } else {
-                    init_time = std::time::Instant::now();
+                    init_time = Instant::now();
                     Duration::new(0, 0)
                 };
 
@@ -128,23 +408,85 @@ fn main() -> Result<(), minifb::Error> {
                 scene.animate(time);
             }
 
-            current_frame = render::trace_some_rays_nonblocking(
-                &scene,
-                render::RenderOpts {
-                    width,
-                    height,
-                    samples: opts.samples,
-                },
-            );
+            current_frame = renderer.render(frame_opts, &mut accum)?;
         }
 
         // Update the window's framebuffer
-        current_frame.flush_to_buffer(&mut buffer);
+        current_frame.flush_to_buffer(&mut accum);
+
+        let stats = current_frame.stats();
+        window.set_title(
+            format!(
+                "{} - {:.2} fups - {} spp - {:.0}% (eta {:.1?}) - {:.2} Mrays/s",
+                TITLE,
+                fups.get(),
+                accum.samples(),
+                stats.progress() * 100.,
+                stats.eta().unwrap_or_default(),
+                stats.rays_per_sec() / 1e6,
+            )
+            .as_str(),
+        );
+        match (view, &denoised) {
+            _ if heatmap => accum.resolve_heatmap_to_buffer(&mut buffer),
+            (None, Some((denoised, _))) => {
+                display.resolve_to_buffer(denoised.iter().copied(), &mut buffer)
+            }
+            // the AOVs would have to be re-rendered every pass while the scene
+            // is moving, so the final image is shown instead
+            (Some(aov), _) if !opts.movement => {
+                let aovs = aovs.get_or_insert_with(|| {
+                    AovBuffers::render(&*scene.read().unwrap(), &frame_opts)
+                });
+                aovs.resolve_to_buffer(aov, &mut buffer);
+            }
+            _ => accum.resolve_to_buffer(&mut buffer, &display),
+        }
         window.update_with_buffer(&buffer)?;
 
         // Check for various live options
         if let Some(keys) = window.get_keys_pressed(minifb::KeyRepeat::Yes) {
             for key in keys {
+                // tone mapping happens after accumulation, so tweaking it
+                // doesn't require throwing away any samples
+                let mut display_updated = true;
+                match key {
+                    Key::T => display.tonemap = display.tonemap.next(),
+                    Key::LeftBracket => display.exposure -= 0.5,
+                    Key::RightBracket => display.exposure += 0.5,
+                    Key::H => {
+                        heatmap = !heatmap;
+                        println!("heatmap {}", if heatmap { "on" } else { "off" });
+                        continue;
+                    }
+                    Key::N => {
+                        denoise = !denoise;
+                        denoised = None;
+                        println!("denoising {}", if denoise { "on" } else { "off" });
+                        continue;
+                    }
+                    Key::V => {
+                        // cycle through the AOVs, and back to the final image
+                        view = match view {
+                            None => Some(Aov::ALL[0]),
+                            Some(aov) => {
+                                let i = Aov::ALL.iter().position(|&a| a == aov).unwrap();
+                                Aov::ALL.get(i + 1).copied()
+                            }
+                        };
+                        match view {
+                            Some(aov) => println!("viewing {} AOV", aov),
+                            None => println!("viewing final image"),
+                        }
+                        continue;
+                    }
+                    _ => display_updated = false,
+                }
+                if display_updated {
+                    println!("{:#?}", display);
+                    continue;
+                }
+
                 let mut opts_updated = true;
                 match key {
                     Key::Space => opts.movement = !opts.movement,
@@ -163,7 +505,9 @@ fn main() -> Result<(), minifb::Error> {
                 }
                 if opts_updated {
                     println!("{:#?}", opts);
-                    buffer = vec![0; width * height];
+                    accum.reset();
+                    aovs = None;
+                    denoised = None;
                     current_frame.invalidate();
                 }
             }
@@ -1,7 +1,6 @@
-use rand::Rng;
-
 use crate::hittable::HitRecord;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::vec3::Vec3;
 
 use super::{Material, MaterialT};
@@ -42,7 +41,12 @@ fn schlick(cosine: f32, ref_idx: f32) -> f32 {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
+    fn scatter(
+        &self,
+        sampler: &mut dyn Sampler,
+        r_in: &Ray,
+        rec: &HitRecord,
+    ) -> Option<(Vec3, Ray)> {
         let reflected = r_in.direction.reflect_through(&rec.normal);
 
         let outward_normal;
@@ -61,7 +65,7 @@ impl Material for Dielectric {
         let scattered = match refract(&r_in.direction, &outward_normal, ni_over_nt) {
             Some(refracted) => {
                 let reflect_prob = schlick(cosine, self.ref_idx);
-                if rand::thread_rng().gen::<f32>() < reflect_prob {
+                if sampler.next_1d() < reflect_prob {
                     Ray::new(rec.p, reflected)
                 } else {
                     Ray::new(rec.p, refracted)
@@ -73,4 +77,8 @@ impl Material for Dielectric {
         let attenuation = Vec3::new(1., 1., 1.); // doesn't absorb anything
         Some((attenuation, scattered))
     }
+
+    fn albedo(&self, _rec: &HitRecord) -> Vec3 {
+        Vec3::new(1., 1., 1.)
+    }
 }
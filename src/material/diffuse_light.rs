@@ -0,0 +1,34 @@
+use crate::hittable::HitRecord;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+
+use super::{Material, MaterialT};
+
+/// Material that emits light uniformly, and doesn't scatter incoming rays
+#[derive(Debug)]
+pub struct DiffuseLight {
+    emit: Vec3,
+}
+
+impl DiffuseLight {
+    /// Return a new DiffuseLight material, emitting `emit` radiance
+    pub fn new_material(emit: Vec3) -> MaterialT {
+        DiffuseLight { emit }.into()
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _sampler: &mut dyn Sampler,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+    ) -> Option<(Vec3, Ray)> {
+        None
+    }
+
+    fn emitted(&self, _rec: &HitRecord) -> Vec3 {
+        self.emit
+    }
+}
@@ -1,6 +1,10 @@
+use std::f32::consts::PI;
+
 use crate::hittable::HitRecord;
 use crate::ray::Ray;
-use crate::util::rand_in_unit_sphere;
+use crate::sampler::Sampler;
+use crate::texture::{Texture, TextureT};
+use crate::util::cosine_hemisphere;
 use crate::vec3::Vec3;
 
 use super::{Material, MaterialT};
@@ -8,23 +12,48 @@ use super::{Material, MaterialT};
 /// Material that scatters incoming rays in random directions.
 #[derive(Debug)]
 pub struct Lambertian {
-    albedo: Vec3,
+    albedo: TextureT,
 }
 
 impl Lambertian {
-    /// Return a new Lambertian material
-    pub fn new_material(albedo: Vec3) -> MaterialT {
-        Lambertian { albedo }.into()
+    /// Return a new Lambertian material, with its albedo given by a texture
+    /// (or a plain color)
+    pub fn new_material(albedo: impl Into<TextureT>) -> MaterialT {
+        Lambertian {
+            albedo: albedo.into(),
+        }
+        .into()
     }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
-        let _ = r_in; // unused, since rays are reflected randomly
-
-        let target = rec.p + rec.normal + rand_in_unit_sphere();
-        let scattered = Ray::new(rec.p, target - rec.p);
-        let attenuation = self.albedo;
+    fn scatter(
+        &self,
+        sampler: &mut dyn Sampler,
+        r_in: &Ray,
+        rec: &HitRecord,
+    ) -> Option<(Vec3, Ray)> {
+        let normal = facing(r_in, rec);
+        let scattered = Ray::new(rec.p, cosine_hemisphere(&normal, sampler.next_2d()));
+        let attenuation = self.albedo(rec);
         Some((attenuation, scattered))
     }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> Option<(Vec3, f32)> {
+        let cos = facing(r_in, rec).dot(dir).max(0.);
+        Some((self.albedo(rec) * (cos / PI), cos / PI))
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Vec3 {
+        self.albedo.value(rec.uv, &rec.p)
+    }
+}
+
+/// Returns the hit's normal, flipped to face the side the ray arrived from
+fn facing(r_in: &Ray, rec: &HitRecord) -> Vec3 {
+    if r_in.direction.dot(&rec.normal) > 0. {
+        -rec.normal
+    } else {
+        rec.normal
+    }
 }
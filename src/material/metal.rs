@@ -1,6 +1,10 @@
+use std::f32::consts::PI;
+
 use crate::hittable::HitRecord;
 use crate::ray::Ray;
-use crate::util::rand_in_unit_sphere;
+use crate::sampler::Sampler;
+use crate::texture::{Texture, TextureT};
+use crate::util::uniform_ball;
 use crate::vec3::Vec3;
 
 use super::{Material, MaterialT};
@@ -8,22 +12,60 @@ use super::{Material, MaterialT};
 /// Material that reflects incoming rays through the hit-point's normal
 #[derive(Debug)]
 pub struct Metal {
-    albedo: Vec3,
+    albedo: TextureT,
     fuzz: f32,
 }
 
 impl Metal {
-    /// Return a new Metal material
-    pub fn new_material(albedo: Vec3, fuzz: f32) -> MaterialT {
-        Metal { albedo, fuzz }.into()
+    /// Return a new Metal material, with its albedo given by a texture (or a
+    /// plain color)
+    pub fn new_material(albedo: impl Into<TextureT>, fuzz: f32) -> MaterialT {
+        Metal {
+            albedo: albedo.into(),
+            fuzz,
+        }
+        .into()
+    }
+
+    /// Returns the probability density (with respect to solid angle) of
+    /// scattering along (unit) direction `dir`, when reflecting along (unit)
+    /// direction `reflected`.
+    ///
+    /// Scattered rays pass through a random point in a ball of radius `fuzz`
+    /// around `reflected`, so the density is the volume of the ball lying
+    /// along `dir` (weighted by its squared distance from the origin),
+    /// relative to the ball's total volume.
+    fn pdf(&self, reflected: &Vec3, dir: &Vec3) -> f32 {
+        // where `dir` enters and exits the ball
+        let b = dir.dot(reflected);
+        let c = 1. - self.fuzz * self.fuzz;
+        let discriminant = b * b - c;
+        if discriminant <= 0. {
+            return 0.;
+        }
+        let h = discriminant.sqrt();
+        let (t0, t1) = ((b - h).max(0.), b + h);
+        if t1 <= 0. {
+            return 0.;
+        }
+
+        // t1^3 - t0^3, without cancellation
+        let cubes = (t1 - t0) * (t1 * t1 + t1 * t0 + t0 * t0);
+        cubes / (4. * PI * self.fuzz.powi(3))
     }
 }
 
 impl Material for Metal {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
+    fn scatter(
+        &self,
+        sampler: &mut dyn Sampler,
+        r_in: &Ray,
+        rec: &HitRecord,
+    ) -> Option<(Vec3, Ray)> {
         let reflected = r_in.direction.normalize().reflect_through(&rec.normal);
-        let scattered = Ray::new(rec.p, reflected + self.fuzz * rand_in_unit_sphere());
-        let attenuation = self.albedo;
+        let fuzz = uniform_ball(sampler.next_2d(), sampler.next_1d());
+        let scattered = Ray::new(rec.p, reflected + self.fuzz * fuzz);
+        let attenuation = self.albedo(rec);
         // TODO: do some personal reasearch into why this check is used
         if scattered.direction.dot(&rec.normal) > 0.0 {
             Some((attenuation, scattered))
@@ -31,4 +73,23 @@ impl Material for Metal {
             None
         }
     }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> Option<(Vec3, f32)> {
+        // perfect mirrors only reflect light from a single direction
+        if self.fuzz <= 0. {
+            return None;
+        }
+
+        let reflected = r_in.direction.normalize().reflect_through(&rec.normal);
+        let pdf = self.pdf(&reflected, dir);
+        // rays scattered into the surface are absorbed
+        if dir.dot(&rec.normal) <= 0. {
+            return Some((Vec3::new(0., 0., 0.), pdf));
+        }
+        Some((self.albedo(rec) * pdf, pdf))
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Vec3 {
+        self.albedo.value(rec.uv, &rec.p)
+    }
 }
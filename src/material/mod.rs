@@ -1,13 +1,16 @@
 use crate::hittable::HitRecord;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::vec3::Vec3;
 
 mod dielectric;
+mod diffuse_light;
 mod lambertian;
 mod metal;
 mod void;
 
 pub use dielectric::Dielectric;
+pub use diffuse_light::DiffuseLight;
 pub use lambertian::Lambertian;
 pub use metal::Metal;
 pub use void::Void;
@@ -15,7 +18,40 @@ pub use void::Void;
 pub trait Material: Send + Sync + std::fmt::Debug {
     /// Given a incoming [Ray] and a [HitRecord], returns None if the Ray is
     /// absorbed, or Some((Attentuation, Scattered Ray))
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)>;
+    fn scatter(
+        &self,
+        sampler: &mut dyn Sampler,
+        r_in: &Ray,
+        rec: &HitRecord,
+    ) -> Option<(Vec3, Ray)>;
+
+    /// Evaluates the material for light arriving from (unit) direction `dir`
+    /// and scattered back along `r_in`. Returns Some((BSDF, pdf)), where the
+    /// BSDF is multiplied by the cosine of the angle between `dir` and the
+    /// normal, and the pdf is the probability density (with respect to solid
+    /// angle) of [Material::scatter] having picked `dir`.
+    ///
+    /// Returns None if the material only scatters light in specific
+    /// directions (e.g: perfect mirrors), and therefore can't be lit by
+    /// sampling lights directly.
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> Option<(Vec3, f32)> {
+        let _ = (r_in, rec, dir);
+        None
+    }
+
+    /// Returns the light emitted by the material at the hit-point.
+    /// Most materials don't emit any light.
+    fn emitted(&self, rec: &HitRecord) -> Vec3 {
+        let _ = rec;
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    /// Returns the material's base color at the hit-point (used for AOVs).
+    /// Defaults to black, for materials that don't reflect any light.
+    fn albedo(&self, rec: &HitRecord) -> Vec3 {
+        let _ = rec;
+        Vec3::new(0.0, 0.0, 0.0)
+    }
 }
 
 cfg_if::cfg_if! {
@@ -29,8 +65,25 @@ cfg_if::cfg_if! {
 macro_rules! impl_ref {
     ($type:ty) => {
         impl Material for $type {
-            fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
-                (**self).scatter(r_in, rec)
+            fn scatter(
+                &self,
+                sampler: &mut dyn Sampler,
+                r_in: &Ray,
+                rec: &HitRecord,
+            ) -> Option<(Vec3, Ray)> {
+                (**self).scatter(sampler, r_in, rec)
+            }
+
+            fn eval(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> Option<(Vec3, f32)> {
+                (**self).eval(r_in, rec, dir)
+            }
+
+            fn emitted(&self, rec: &HitRecord) -> Vec3 {
+                (**self).emitted(rec)
+            }
+
+            fn albedo(&self, rec: &HitRecord) -> Vec3 {
+                (**self).albedo(rec)
             }
         }
     };
@@ -41,7 +94,7 @@ cfg_if::cfg_if! {
         // When using enum dispatch:
         // - create the enum
         // - Implements Material for &enum and &mut enum
-        // - Implements Into<enum> for each enum variant
+        // - Implements From<variant> for enum
         // - Implements enum dispatch by implementing Material on the enum
         macro_rules! materials {
             (
@@ -59,18 +112,39 @@ cfg_if::cfg_if! {
                 impl_ref!(&mut $enum_name);
 
                 $(
-                    impl Into<$enum_name> for $mat_type {
-                        fn into(self) -> $enum_name {
-                            $enum_name::$mat_name(self)
+                    impl From<$mat_type> for $enum_name {
+                        fn from(x: $mat_type) -> $enum_name {
+                            $enum_name::$mat_name(x)
                         }
                     }
                 )*
 
                 impl Material for $enum_name {
-                    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
+                    fn scatter(&self, sampler: &mut dyn Sampler, r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
+                        use self::$enum_name::*;
+                        match self {
+                            $($mat_name(x) => x.scatter(sampler, r_in, rec),)*
+                        }
+                    }
+
+                    fn eval(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> Option<(Vec3, f32)> {
+                        use self::$enum_name::*;
+                        match self {
+                            $($mat_name(x) => x.eval(r_in, rec, dir),)*
+                        }
+                    }
+
+                    fn emitted(&self, rec: &HitRecord) -> Vec3 {
+                        use self::$enum_name::*;
+                        match self {
+                            $($mat_name(x) => x.emitted(rec),)*
+                        }
+                    }
+
+                    fn albedo(&self, rec: &HitRecord) -> Vec3 {
                         use self::$enum_name::*;
                         match self {
-                            $($mat_name(x) => x.scatter(r_in, rec),)*
+                            $($mat_name(x) => x.albedo(rec),)*
                         }
                     }
                 }
@@ -79,7 +153,7 @@ cfg_if::cfg_if! {
     } else {
         // When using dynamic dispatch:
         // - Implement Material for various Box<dyn>, &dyn, and &mut dyn Material
-        // - Implement Into<Box<dyn Material>> for each Material type
+        // - Implement From<Material> for Box<dyn Material> for each Material type
         macro_rules! materials {
             (
                 $(#[$meta:meta])*
@@ -92,9 +166,9 @@ cfg_if::cfg_if! {
                 impl_ref!(&mut dyn Material);
 
                 $(
-                    impl Into<Box<dyn Material>> for $mat_type {
-                        fn into(self) -> Box<dyn Material> {
-                            Box::new(self)
+                    impl From<$mat_type> for Box<dyn Material> {
+                        fn from(x: $mat_type) -> Box<dyn Material> {
+                            Box::new(x)
                         }
                     }
                 )*
@@ -107,6 +181,7 @@ materials! {
     #[derive(Debug)]
     pub enum Materials {
         Dielectric(Dielectric),
+        DiffuseLight(DiffuseLight),
         Lambertian(Lambertian),
         Metal(Metal),
         Void(Void),
@@ -1,5 +1,6 @@
 use crate::hittable::HitRecord;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::vec3::Vec3;
 
 use super::{Material, MaterialT};
@@ -16,7 +17,12 @@ impl Void {
 }
 
 impl Material for Void {
-    fn scatter(&self, _r_in: &Ray, _rec: &HitRecord) -> Option<(Vec3, Ray)> {
+    fn scatter(
+        &self,
+        _sampler: &mut dyn Sampler,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+    ) -> Option<(Vec3, Ray)> {
         None
     }
 }
@@ -0,0 +1,553 @@
+//! Wavefront OBJ (and accompanying MTL) mesh importer.
+//!
+//! Each group (`g` / `o`) and material (`usemtl`) combination in the OBJ file
+//! is imported as a separate [ObjMesh]. Polygonal faces are triangulated as
+//! fans, and MTL materials are approximated using the existing materials (see
+//! [Mtl::to_material]).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::hittable::{HittableT, TriangleMesh};
+use crate::material::{self, MaterialT};
+use crate::vec3::Vec3;
+
+/// Error returned when an OBJ or MTL file fails to load
+#[derive(Debug)]
+pub struct ObjError {
+    /// File in which the error occurred
+    pub path: PathBuf,
+    /// Line on which the error occurred (if applicable)
+    pub line: Option<usize>,
+    pub msg: String,
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.msg),
+            None => write!(f, "{}: {}", self.path.display(), self.msg),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+/// A material parsed from an MTL file
+#[derive(Debug, Clone)]
+pub struct Mtl {
+    pub name: String,
+    /// diffuse color
+    pub kd: Vec3,
+    /// specular color
+    pub ks: Vec3,
+    /// emissive color
+    pub ke: Vec3,
+    /// specular exponent
+    pub ns: f32,
+    /// index of refraction
+    pub ni: f32,
+    /// opacity
+    pub d: f32,
+    /// illumination model
+    pub illum: u32,
+}
+
+impl Default for Mtl {
+    fn default() -> Mtl {
+        Mtl {
+            name: String::new(),
+            kd: Vec3::new(0.8, 0.8, 0.8),
+            ks: Vec3::new(0., 0., 0.),
+            ke: Vec3::new(0., 0., 0.),
+            ns: 0.,
+            ni: 1.,
+            d: 1.,
+            illum: 2,
+        }
+    }
+}
+
+fn max_component(v: &Vec3) -> f32 {
+    v.x.max(v.y).max(v.z)
+}
+
+impl Mtl {
+    /// Approximate the MTL material using one of the existing materials:
+    ///
+    /// - emissive materials (non-zero `Ke`) are mapped to a
+    ///   [DiffuseLight](material::DiffuseLight)
+    /// - transparent materials (`d < 1`, or a refractive `illum` model) are
+    ///   mapped to a [Dielectric](material::Dielectric) with index `Ni`
+    /// - specular materials (`illum 3`, or a brighter `Ks` than `Kd`) are mapped
+    ///   to [Metal](material::Metal), with the fuzz derived from `Ns`
+    /// - everything else is mapped to [Lambertian](material::Lambertian)
+    pub fn to_material(&self) -> MaterialT {
+        if max_component(&self.ke) > 0. {
+            material::DiffuseLight::new_material(self.ke)
+        } else if self.d < 1. || matches!(self.illum, 4 | 6 | 7 | 9) {
+            material::Dielectric::new_material(self.ni)
+        } else if self.illum == 3 || max_component(&self.ks) > max_component(&self.kd) {
+            // rough approximation of Phong exponent -> roughness
+            let fuzz = (2. / (self.ns + 2.)).sqrt();
+            material::Metal::new_material(self.ks, fuzz)
+        } else {
+            material::Lambertian::new_material(self.kd)
+        }
+    }
+}
+
+/// A triangle mesh imported from an OBJ file.
+#[derive(Debug)]
+pub struct ObjMesh {
+    /// name of the group / object the mesh came from
+    pub name: String,
+    pub positions: Vec<Vec3>,
+    /// per-vertex normals (empty if the mesh didn't specify normals for every
+    /// vertex)
+    pub normals: Vec<Vec3>,
+    /// per-vertex texture coordinates (empty if the mesh didn't specify
+    /// texture coordinates for every vertex)
+    pub texcoords: Vec<[f32; 2]>,
+    pub indices: Vec<[usize; 3]>,
+    pub material: Mtl,
+}
+
+impl ObjMesh {
+    /// Convert the mesh into a hittable [TriangleMesh], using its MTL material
+    pub fn into_hittable(self) -> HittableT {
+        let material = self.material.to_material();
+        self.into_hittable_with(material)
+    }
+
+    /// Convert the mesh into a hittable [TriangleMesh], overriding its material
+    pub fn into_hittable_with(self, material: MaterialT) -> HittableT {
+        TriangleMesh::new_hittable(
+            self.positions,
+            self.normals,
+            self.texcoords,
+            self.indices,
+            material,
+        )
+    }
+}
+
+/// (position, texcoord, normal) indices of a face vertex
+type VertexKey = (usize, Option<usize>, Option<usize>);
+
+/// Accumulates the faces of a single group / material
+#[derive(Default)]
+struct MeshBuilder {
+    name: String,
+    material: Option<String>,
+    vertices: Vec<VertexKey>,
+    vertex_map: HashMap<VertexKey, usize>,
+    indices: Vec<[usize; 3]>,
+}
+
+impl MeshBuilder {
+    fn vertex(&mut self, key: VertexKey) -> usize {
+        let vertices = &mut self.vertices;
+        *self.vertex_map.entry(key).or_insert_with(|| {
+            vertices.push(key);
+            vertices.len() - 1
+        })
+    }
+}
+
+/// OBJ parser state
+#[derive(Default)]
+struct ObjParser {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    texcoords: Vec<[f32; 2]>,
+    materials: HashMap<String, Mtl>,
+    current: MeshBuilder,
+    meshes: Vec<ObjMesh>,
+    /// Problems with the current line which don't stop the file from loading
+    warnings: Vec<String>,
+}
+
+impl ObjParser {
+    /// Finish the current mesh, and start a new one
+    fn next_mesh(&mut self, name: String, material: Option<String>) {
+        let builder = std::mem::replace(
+            &mut self.current,
+            MeshBuilder {
+                name,
+                material,
+                ..MeshBuilder::default()
+            },
+        );
+
+        if builder.indices.is_empty() {
+            return;
+        }
+
+        let vertices = builder.vertices;
+        let normals = if vertices.iter().all(|(_, _, vn)| vn.is_some()) {
+            vertices
+                .iter()
+                .map(|(_, _, vn)| self.normals[vn.unwrap()])
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let texcoords = if vertices.iter().all(|(_, vt, _)| vt.is_some()) {
+            vertices
+                .iter()
+                .map(|(_, vt, _)| self.texcoords[vt.unwrap()])
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        self.meshes.push(ObjMesh {
+            name: builder.name,
+            positions: vertices
+                .iter()
+                .map(|(v, _, _)| self.positions[*v])
+                .collect(),
+            normals,
+            texcoords,
+            indices: builder.indices,
+            material: builder
+                .material
+                .and_then(|name| self.materials.get(&name).cloned())
+                .unwrap_or_default(),
+        });
+    }
+
+    /// Parse a single face vertex (`v`, `v/vt`, `v//vn`, or `v/vt/vn`)
+    fn face_vertex(&self, s: &str) -> Result<VertexKey, String> {
+        let mut parts = s.split('/');
+        let v = parts.next().unwrap_or("");
+        let vt = parts.next().filter(|s| !s.is_empty());
+        let vn = parts.next().filter(|s| !s.is_empty());
+
+        Ok((
+            resolve_index(v, self.positions.len(), "vertex")?,
+            vt.map(|vt| resolve_index(vt, self.texcoords.len(), "texture coordinate"))
+                .transpose()?,
+            vn.map(|vn| resolve_index(vn, self.normals.len(), "normal"))
+                .transpose()?,
+        ))
+    }
+
+    fn parse_line(&mut self, dir: &Path, keyword: &str, args: &[&str]) -> Result<(), ParseError> {
+        match keyword {
+            "v" => self.positions.push(parse_vec3(args)?),
+            "vn" => self.normals.push(parse_vec3(args)?),
+            "vt" => {
+                let uv = parse_floats(args, 1)?;
+                self.texcoords
+                    .push([uv[0], uv.get(1).copied().unwrap_or(0.)]);
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err("face must have at least 3 vertices".to_string().into());
+                }
+                let mut face = Vec::new();
+                for s in args {
+                    let key = self.face_vertex(s)?;
+                    face.push(self.current.vertex(key));
+                }
+                // triangulate as a fan
+                for i in 1..face.len() - 1 {
+                    self.current.indices.push([face[0], face[i], face[i + 1]]);
+                }
+            }
+            "g" | "o" => {
+                let material = self.current.material.clone();
+                self.next_mesh(args.join(" "), material);
+            }
+            "usemtl" => {
+                let name = args.join(" ");
+                // (plenty of exporters reference materials they never write
+                // out, so this isn't worth failing over)
+                if !self.materials.contains_key(&name) {
+                    self.warnings.push(format!(
+                        "undefined material '{}' (using the default material)",
+                        name
+                    ));
+                }
+                let group = self.current.name.clone();
+                self.next_mesh(group, Some(name));
+            }
+            "mtllib" => {
+                for file in args {
+                    for mtl in load_mtl(&dir.join(file))? {
+                        self.materials.insert(mtl.name.clone(), mtl);
+                    }
+                }
+            }
+            // smoothing groups, lines, points, etc... are ignored
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Errors encountered while parsing a line. Errors from nested files (i.e: MTL
+/// libraries) already carry their own location.
+enum ParseError {
+    Msg(String),
+    Nested(ObjError),
+}
+
+impl From<String> for ParseError {
+    fn from(msg: String) -> ParseError {
+        ParseError::Msg(msg)
+    }
+}
+
+impl From<ObjError> for ParseError {
+    fn from(e: ObjError) -> ParseError {
+        ParseError::Nested(e)
+    }
+}
+
+/// Resolve a 1-based (or negative, relative) OBJ index into a 0-based index
+fn resolve_index(s: &str, len: usize, what: &str) -> Result<usize, String> {
+    let i = s
+        .parse::<isize>()
+        .map_err(|_| format!("invalid {} index '{}'", what, s))?;
+    let resolved = if i < 0 { len as isize + i } else { i - 1 };
+    if resolved < 0 || resolved >= len as isize {
+        return Err(format!("{} index {} is out of bounds", what, i));
+    }
+    Ok(resolved as usize)
+}
+
+fn parse_floats(args: &[&str], min: usize) -> Result<Vec<f32>, String> {
+    if args.len() < min {
+        return Err(format!("expected at least {} values", min));
+    }
+    args.iter()
+        .map(|s| s.parse().map_err(|_| format!("invalid number '{}'", s)))
+        .collect()
+}
+
+fn parse_vec3(args: &[&str]) -> Result<Vec3, String> {
+    let v = parse_floats(args, 3)?;
+    Ok(Vec3::new(v[0], v[1], v[2]))
+}
+
+/// Returns the (1-based) line numbers and contents of all the non-empty lines
+/// in a file, with comments stripped.
+fn read_lines(path: &Path) -> Result<Vec<(usize, String)>, ObjError> {
+    let text = std::fs::read_to_string(path).map_err(|e| ObjError {
+        path: path.into(),
+        line: None,
+        msg: e.to_string(),
+    })?;
+
+    Ok(text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.split('#').next().unwrap().trim().to_string()))
+        .filter(|(_, line)| !line.is_empty())
+        .collect())
+}
+
+/// Load all the materials defined in an MTL file
+pub fn load_mtl(path: &Path) -> Result<Vec<Mtl>, ObjError> {
+    let mut materials: Vec<Mtl> = Vec::new();
+
+    for (line_no, line) in read_lines(path)? {
+        let mut words = line.split_whitespace();
+        let keyword = words.next().unwrap();
+        let args = words.collect::<Vec<_>>();
+
+        let err = |msg: String| ObjError {
+            path: path.into(),
+            line: Some(line_no),
+            msg,
+        };
+
+        if keyword == "newmtl" {
+            materials.push(Mtl {
+                name: args.join(" "),
+                ..Mtl::default()
+            });
+            continue;
+        }
+
+        let mtl = match materials.last_mut() {
+            Some(mtl) => mtl,
+            None => return Err(err(format!("'{}' before any 'newmtl'", keyword))),
+        };
+
+        match keyword {
+            "Kd" => mtl.kd = parse_vec3(&args).map_err(err)?,
+            "Ks" => mtl.ks = parse_vec3(&args).map_err(err)?,
+            "Ke" => mtl.ke = parse_vec3(&args).map_err(err)?,
+            "Ns" => mtl.ns = parse_floats(&args, 1).map_err(err)?[0],
+            "Ni" => mtl.ni = parse_floats(&args, 1).map_err(err)?[0],
+            "d" => mtl.d = parse_floats(&args, 1).map_err(err)?[0],
+            "Tr" => mtl.d = 1. - parse_floats(&args, 1).map_err(err)?[0],
+            "illum" => {
+                mtl.illum = match args.first().map(|s| s.parse()) {
+                    Some(Ok(illum)) => illum,
+                    _ => return Err(err("invalid illumination model".to_string())),
+                }
+            }
+            // texture maps, etc... are ignored
+            _ => {}
+        }
+    }
+
+    Ok(materials)
+}
+
+/// Load all the meshes in an OBJ file (and any MTL libraries it references)
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<ObjMesh>, ObjError> {
+    let path = path.as_ref();
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut parser = ObjParser::default();
+    for (line_no, line) in read_lines(path)? {
+        let mut words = line.split_whitespace();
+        let keyword = words.next().unwrap();
+        let args = words.collect::<Vec<_>>();
+
+        let result = parser.parse_line(dir, keyword, &args);
+        for msg in parser.warnings.drain(..) {
+            let warning = ObjError {
+                path: path.into(),
+                line: Some(line_no),
+                msg,
+            };
+            eprintln!("warning: {}", warning);
+        }
+        match result {
+            Ok(()) => {}
+            Err(ParseError::Nested(e)) => return Err(e),
+            Err(ParseError::Msg(msg)) => {
+                return Err(ObjError {
+                    path: path.into(),
+                    line: Some(line_no),
+                    msg,
+                })
+            }
+        }
+    }
+    parser.next_mesh(String::new(), None);
+
+    Ok(parser.meshes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write out the given files into a fresh temporary directory, and return
+    /// the path to the first one
+    fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vrai-tracer-obj-{}", test));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, contents) in files {
+            std::fs::write(dir.join(name), contents).unwrap();
+        }
+        dir.join(files[0].0)
+    }
+
+    #[test]
+    fn indices_are_resolved() {
+        assert_eq!(resolve_index("1", 3, "vertex"), Ok(0));
+        assert_eq!(resolve_index("3", 3, "vertex"), Ok(2));
+        // relative to the end
+        assert_eq!(resolve_index("-1", 3, "vertex"), Ok(2));
+        assert_eq!(resolve_index("-3", 3, "vertex"), Ok(0));
+
+        for i in &["0", "4", "-4", "x", ""] {
+            assert!(resolve_index(i, 3, "vertex").is_err(), "{}", i);
+        }
+    }
+
+    #[test]
+    fn polygons_are_triangulated_as_fans() {
+        let path = write_files(
+            "fans",
+            &[(
+                "pentagon.obj",
+                "v 0 0 0\nv 1 0 0\nv 2 1 0\nv 1 2 0\nv 0 1 0\nf 1 2 3 -2 -1\n",
+            )],
+        );
+        let meshes = load(path).unwrap();
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].positions.len(), 5);
+        assert_eq!(meshes[0].indices, vec![[0, 1, 2], [0, 2, 3], [0, 3, 4]]);
+    }
+
+    #[test]
+    fn vertices_are_shared_within_a_mesh() {
+        let path = write_files(
+            "shared",
+            &[(
+                "quad.obj",
+                "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\n\
+                 f 1/1/1 2/1/1 3/1/1\nf 1/1/1 3/1/1 4/1/1\n",
+            )],
+        );
+        let meshes = load(path).unwrap();
+        assert_eq!(meshes[0].positions.len(), 4);
+        assert_eq!(meshes[0].normals.len(), 4);
+        assert_eq!(meshes[0].texcoords.len(), 4);
+        assert_eq!(meshes[0].indices, vec![[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn groups_and_materials_split_meshes() {
+        let path = write_files(
+            "groups",
+            &[
+                (
+                    "groups.obj",
+                    "mtllib groups.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\n\
+                     g a\nf 1 2 3\nusemtl light\nf 1 2 3\ng b\nf 1 2 3\n\
+                     usemtl missing\nf 1 2 3\n",
+                ),
+                ("groups.mtl", "newmtl light\nKe 1 1 1\n"),
+            ],
+        );
+        let meshes = load(path).unwrap();
+        let names = meshes.iter().map(|m| m.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["a", "a", "b", "b"]);
+        assert_eq!(meshes[0].material.name, "");
+        // the material carries over to the next group
+        assert_eq!(meshes[1].material.name, "light");
+        assert_eq!(meshes[2].material.name, "light");
+        // undefined materials fall back to the default
+        assert_eq!(meshes[3].material.name, "");
+    }
+
+    #[test]
+    fn errors_report_their_location() {
+        let path = write_files(
+            "errors",
+            &[
+                ("bad_index.obj", "v 0 0 0\nv 1 0 0\n\nf 1 2 3\n"),
+                ("bad_mtl.obj", "# materials\nmtllib bad.mtl\n"),
+                ("bad.mtl", "newmtl a\nKd 1 x 1\n"),
+            ],
+        );
+        let err = load(&path).unwrap_err();
+        assert_eq!(err.line, Some(4));
+        assert_eq!(
+            err.to_string(),
+            format!("{}:4: vertex index 3 is out of bounds", path.display())
+        );
+
+        // errors in MTL libraries point into the library itself
+        let err = load(path.with_file_name("bad_mtl.obj")).unwrap_err();
+        assert_eq!(err.path, path.with_file_name("bad.mtl"));
+        assert_eq!(err.line, Some(2));
+        assert!(err.to_string().ends_with("bad.mtl:2: invalid number 'x'"));
+
+        let err = load(path.with_file_name("missing.obj")).unwrap_err();
+        assert_eq!(err.line, None);
+    }
+}
@@ -0,0 +1,181 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::vec3::Vec3;
+
+/// `PixelType::FLOAT`
+const PIXEL_TYPE_FLOAT: i32 = 2;
+
+/// Write a header attribute
+fn write_attr(w: &mut impl Write, name: &str, ty: &str, value: &[u8]) -> io::Result<()> {
+    w.write_all(name.as_bytes())?;
+    w.write_all(&[0])?;
+    w.write_all(ty.as_bytes())?;
+    w.write_all(&[0])?;
+    w.write_all(&(value.len() as i32).to_le_bytes())?;
+    w.write_all(value)
+}
+
+/// Write linear radiance as an uncompressed, single-part, scanline OpenEXR
+/// file (with 32-bit float R, G, and B channels).
+pub fn write_exr(
+    path: impl AsRef<Path>,
+    width: usize,
+    height: usize,
+    pixels: &[Vec3],
+) -> io::Result<()> {
+    let mut header = Vec::new();
+
+    // magic number, followed by version 2 (single-part scanline)
+    header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01]);
+    header.extend_from_slice(&2i32.to_le_bytes());
+
+    // channels must be listed in alphabetical order
+    let mut chlist = Vec::new();
+    for name in &["B", "G", "R"] {
+        chlist.extend_from_slice(name.as_bytes());
+        chlist.push(0);
+        chlist.extend_from_slice(&PIXEL_TYPE_FLOAT.to_le_bytes());
+        chlist.extend_from_slice(&[0, 0, 0, 0]); // pLinear + reserved
+        chlist.extend_from_slice(&1i32.to_le_bytes()); // xSampling
+        chlist.extend_from_slice(&1i32.to_le_bytes()); // ySampling
+    }
+    chlist.push(0);
+    write_attr(&mut header, "channels", "chlist", &chlist)?;
+
+    write_attr(&mut header, "compression", "compression", &[0])?;
+
+    let window = [0, 0, width as i32 - 1, height as i32 - 1]
+        .iter()
+        .flat_map(|x| x.to_le_bytes().to_vec())
+        .collect::<Vec<u8>>();
+    write_attr(&mut header, "dataWindow", "box2i", &window)?;
+    write_attr(&mut header, "displayWindow", "box2i", &window)?;
+
+    // INCREASING_Y
+    write_attr(&mut header, "lineOrder", "lineOrder", &[0])?;
+    write_attr(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1f32.to_le_bytes(),
+    )?;
+    write_attr(&mut header, "screenWindowCenter", "v2f", &[0; 8])?;
+    write_attr(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1f32.to_le_bytes(),
+    )?;
+    header.push(0);
+
+    let mut w = BufWriter::new(File::create(path)?);
+    w.write_all(&header)?;
+
+    // offset table: uncompressed files store one scanline per chunk, each of
+    // which consists of the y coordinate, data size, and channel data.
+    let line_size = width * 3 * 4;
+    let chunk_size = 4 + 4 + line_size;
+    let table_end = header.len() + height * 8;
+    for y in 0..height {
+        let offset = (table_end + y * chunk_size) as u64;
+        w.write_all(&offset.to_le_bytes())?;
+    }
+
+    for (y, row) in pixels.chunks(width).enumerate() {
+        w.write_all(&(y as i32).to_le_bytes())?;
+        w.write_all(&(line_size as i32).to_le_bytes())?;
+        // channel data is stored planar, in the same order as the chlist
+        for channel in &[|c: &Vec3| c.z, |c: &Vec3| c.y, |c: &Vec3| c.x] {
+            for px in row {
+                w.write_all(&channel(px).to_le_bytes())?;
+            }
+        }
+    }
+
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use super::*;
+
+    fn read_i32(data: &[u8], at: usize) -> i32 {
+        i32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+    }
+
+    /// Read a null terminated string, returning it along with the offset past
+    /// its end
+    fn read_str(data: &[u8], at: usize) -> (&str, usize) {
+        let len = data[at..].iter().position(|&b| b == 0).unwrap();
+        (
+            std::str::from_utf8(&data[at..at + len]).unwrap(),
+            at + len + 1,
+        )
+    }
+
+    #[test]
+    fn header_and_offsets_are_consistent() {
+        let (width, height) = (3, 2);
+        let pixels = (0..width * height)
+            .map(|i| Vec3::new(i as f32, 10. + i as f32, 20. + i as f32))
+            .collect::<Vec<_>>();
+        let path = std::env::temp_dir().join("vrai-tracer-header.exr");
+        write_exr(&path, width, height, &pixels).unwrap();
+        let data = std::fs::read(&path).unwrap();
+
+        assert_eq!(&data[..4], &[0x76, 0x2f, 0x31, 0x01]);
+        assert_eq!(read_i32(&data, 4), 2);
+
+        // attributes, up until the empty name terminating the header
+        let mut attrs = Vec::new();
+        let mut at = 8;
+        loop {
+            let (name, next) = read_str(&data, at);
+            if name.is_empty() {
+                at = next;
+                break;
+            }
+            let (ty, next) = read_str(&data, next);
+            let size = read_i32(&data, next) as usize;
+            attrs.push((name, ty, &data[next + 4..next + 4 + size]));
+            at = next + 4 + size;
+        }
+        let names = attrs.iter().map(|(name, _, _)| *name).collect::<Vec<_>>();
+        for required in &[
+            "channels",
+            "compression",
+            "dataWindow",
+            "displayWindow",
+            "lineOrder",
+            "pixelAspectRatio",
+            "screenWindowCenter",
+            "screenWindowWidth",
+        ] {
+            assert!(names.contains(required), "missing {}", required);
+        }
+        let (_, ty, window) = attrs.iter().find(|a| a.0 == "dataWindow").unwrap();
+        assert_eq!(*ty, "box2i");
+        let window = (0..4).map(|i| read_i32(window, i * 4)).collect::<Vec<_>>();
+        assert_eq!(window, vec![0, 0, width as i32 - 1, height as i32 - 1]);
+
+        // the offset table points at each scanline in turn, the last of which
+        // ends the file
+        let line_size = width * 3 * 4;
+        for y in 0..height {
+            let offset = u64::from_le_bytes(data[at + y * 8..at + y * 8 + 8].try_into().unwrap());
+            let offset = offset as usize;
+            assert_eq!(read_i32(&data, offset), y as i32);
+            assert_eq!(read_i32(&data, offset + 4), line_size as i32);
+            // (planar B, G, then R)
+            let first_blue = f32::from_le_bytes(data[offset + 8..offset + 12].try_into().unwrap());
+            assert_eq!(first_blue, pixels[y * width].z);
+            if y == height - 1 {
+                assert_eq!(offset + 8 + line_size, data.len());
+            }
+        }
+    }
+}
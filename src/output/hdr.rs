@@ -0,0 +1,157 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::vec3::Vec3;
+
+/// Encode a single pixel as RGBE (a shared exponent, and 8-bit mantissas)
+fn rgb_to_rgbe(c: &Vec3) -> [u8; 4] {
+    let m = c.x.max(c.y).max(c.z);
+    if m.is_nan() || m <= 1e-32 {
+        return [0; 4];
+    }
+
+    // m = f * 2^e, with f in [0.5, 1)
+    let mut e = m.log2().floor() as i32 + 1;
+    if m / 2f32.powi(e) >= 1. {
+        e += 1;
+    }
+    let scale = 256. / 2f32.powi(e);
+    let mantissa = |x: f32| (x.max(0.) * scale).min(255.) as u8;
+
+    [mantissa(c.x), mantissa(c.y), mantissa(c.z), (e + 128) as u8]
+}
+
+/// Write a single channel of a scanline using the adaptive run-length
+/// encoding (runs of identical values, interspersed with literal values).
+fn write_rle_channel(w: &mut impl Write, data: &[u8]) -> io::Result<()> {
+    const MIN_RUN: usize = 4;
+
+    let mut i = 0;
+    while i < data.len() {
+        // find the next run worth encoding
+        let mut run_start = i;
+        let mut run_len = 0;
+        while run_start < data.len() {
+            run_len = data[run_start..]
+                .iter()
+                .take(127)
+                .take_while(|&&x| x == data[run_start])
+                .count();
+            if run_len >= MIN_RUN {
+                break;
+            }
+            run_start += run_len;
+        }
+
+        // literals leading up to the run
+        for lit in data[i..run_start].chunks(128) {
+            w.write_all(&[lit.len() as u8])?;
+            w.write_all(lit)?;
+        }
+
+        if run_start < data.len() {
+            w.write_all(&[128 + run_len as u8, data[run_start]])?;
+            i = run_start + run_len;
+        } else {
+            i = run_start;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write linear radiance as a (run-length encoded) Radiance `.hdr` file
+pub fn write_hdr(
+    path: impl AsRef<Path>,
+    width: usize,
+    height: usize,
+    pixels: &[Vec3],
+) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+
+    write!(
+        w,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        height, width
+    )?;
+
+    // scanlines that are too short / long can't be run-length encoded
+    let use_rle = (8..0x8000).contains(&width);
+
+    let mut channels: [Vec<u8>; 4] = Default::default();
+    for row in pixels.chunks(width) {
+        let rgbe = row.iter().map(rgb_to_rgbe);
+
+        if !use_rle {
+            for px in rgbe {
+                w.write_all(&px)?;
+            }
+            continue;
+        }
+
+        channels.iter_mut().for_each(Vec::clear);
+        for px in rgbe {
+            for (channel, &x) in channels.iter_mut().zip(px.iter()) {
+                channel.push(x);
+            }
+        }
+
+        w.write_all(&[2, 2, (width >> 8) as u8, width as u8])?;
+        for channel in &channels {
+            write_rle_channel(&mut w, channel)?;
+        }
+    }
+
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(name: &str, width: usize, height: usize, pixels: &[Vec3]) -> Vec<Vec3> {
+        let path = std::env::temp_dir().join(format!("vrai-tracer-{}.hdr", name));
+        write_hdr(&path, width, height, pixels).unwrap();
+        let image = crate::hdr::read(&path).unwrap();
+        assert_eq!((image.width, image.height), (width, height));
+        image.pixels
+    }
+
+    fn assert_close(a: &[Vec3], b: &[Vec3]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b) {
+            // (8-bit mantissas, relative to the brightest channel)
+            let tolerance = a.x.max(a.y).max(a.z) / 128.;
+            for (x, y) in [(a.x, b.x), (a.y, b.y), (a.z, b.z)] {
+                assert!((x - y).abs() <= tolerance, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn rgbe_round_trip() {
+        assert_eq!(rgb_to_rgbe(&Vec3::new(0., 0., 0.)), [0; 4]);
+        assert_eq!(rgb_to_rgbe(&Vec3::new(1e-40, 0., 0.)), [0; 4]);
+        assert_eq!(rgb_to_rgbe(&Vec3::new(1., 0.5, 0.)), [128, 64, 0, 129]);
+        // negative channels are clamped to 0
+        assert_eq!(rgb_to_rgbe(&Vec3::new(1., -0.5, 0.)), [128, 0, 0, 129]);
+
+        // flat scanlines (too narrow to be run-length encoded)
+        let pixels = (0..15)
+            .map(|i| Vec3::new(i as f32, 0.5 / (i + 1) as f32, 100. * i as f32))
+            .collect::<Vec<_>>();
+        assert_close(&pixels, &round_trip("flat", 5, 3, &pixels));
+
+        // run-length encoded scanlines, with both runs and literals (and runs
+        // longer than can be encoded at once)
+        let pixels = (0..300 * 2)
+            .map(|i| match i % 300 {
+                x if x < 10 => Vec3::new(x as f32, 1., 2.),
+                x if x < 200 => Vec3::new(0.5, 0.5, 0.5),
+                x => Vec3::new(0.1, x as f32, 0.),
+            })
+            .collect::<Vec<_>>();
+        assert_close(&pixels, &round_trip("rle", 300, 2, &pixels));
+    }
+}
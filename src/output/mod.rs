@@ -0,0 +1,83 @@
+//! Writers for saving rendered frames to disk
+
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+mod exr;
+mod hdr;
+mod pfm;
+
+pub use exr::write_exr;
+pub use hdr::write_hdr;
+pub use pfm::write_pfm;
+
+/// Supported output image formats
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Format {
+    /// 8-bit, tone mapped
+    Png,
+    /// Radiance RGBE, linear
+    Hdr,
+    /// Portable Float Map, linear
+    Pfm,
+    /// OpenEXR, linear
+    Exr,
+}
+
+impl Format {
+    /// Pick an output format based on the extension of `path`
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let ext = path.extension().and_then(OsStr::to_str)?;
+        match ext.to_ascii_lowercase().as_str() {
+            "png" => Some(Format::Png),
+            "hdr" => Some(Format::Hdr),
+            "pfm" => Some(Format::Pfm),
+            "exr" => Some(Format::Exr),
+            _ => None,
+        }
+    }
+}
+
+/// Encode a framebuffer (in the same `0RGB` format used by the live preview)
+/// as an 8-bit RGB PNG.
+pub fn write_png(
+    path: impl AsRef<Path>,
+    width: usize,
+    height: usize,
+    buffer: &[u32],
+) -> io::Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+
+    let mut encoder = png::Encoder::new(file, width as u32, height as u32);
+    encoder.set_color(png::ColorType::RGB);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut data = Vec::with_capacity(buffer.len() * 3);
+    for px in buffer {
+        let [b, g, r, _] = px.to_le_bytes();
+        data.extend_from_slice(&[r, g, b]);
+    }
+
+    encoder.write_header()?.write_image_data(&data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn png_channels_are_unpacked() {
+        let path = std::env::temp_dir().join("vrai-tracer-channels.png");
+        write_png(&path, 2, 1, &[0x00102030, 0x00ff8001]).unwrap();
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let (info, mut reader) = decoder.read_info().unwrap();
+        assert_eq!((info.width, info.height), (2, 1));
+        let mut data = vec![0; info.buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+        assert_eq!(data, vec![0x10, 0x20, 0x30, 0xff, 0x80, 0x01]);
+    }
+}
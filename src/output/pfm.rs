@@ -0,0 +1,59 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::vec3::Vec3;
+
+/// Write linear radiance as a (color) Portable Float Map
+pub fn write_pfm(
+    path: impl AsRef<Path>,
+    width: usize,
+    height: usize,
+    pixels: &[Vec3],
+) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+
+    // a negative scale indicates little-endian data
+    write!(w, "PF\n{} {}\n-1.0\n", width, height)?;
+
+    // PFM scanlines are stored bottom to top
+    for row in pixels.chunks(width).rev() {
+        for px in row {
+            for c in &[px.x, px.y, px.z] {
+                w.write_all(&c.to_le_bytes())?;
+            }
+        }
+    }
+
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_are_stored_bottom_to_top() {
+        let pixels = (0..6)
+            .map(|i| Vec3::new(i as f32, 0.5, -1.))
+            .collect::<Vec<_>>();
+        let path = std::env::temp_dir().join("vrai-tracer-rows.pfm");
+        write_pfm(&path, 2, 3, &pixels).unwrap();
+        let data = std::fs::read(&path).unwrap();
+
+        let header = b"PF\n2 3\n-1.0\n";
+        assert_eq!(&data[..header.len()], header);
+        let floats = data[header.len()..]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            floats,
+            vec![
+                4., 0.5, -1., 5., 0.5, -1., // bottom row
+                2., 0.5, -1., 3., 0.5, -1., //
+                0., 0.5, -1., 1., 0.5, -1., // top row
+            ]
+        );
+    }
+}
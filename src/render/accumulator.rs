@@ -0,0 +1,121 @@
+use crate::vec3::Vec3;
+
+use super::adaptive::{heat_color, RunningStats};
+use super::{AsColorExt, DisplayOpts};
+
+/// High-dynamic-range accumulation buffer.
+///
+/// Keeps a running sum of the (linear) radiance samples taken at each pixel,
+/// so that successive render passes over an unchanged scene keep refining the
+/// image.
+#[derive(Debug)]
+pub struct Accumulator {
+    width: usize,
+    height: usize,
+    /// sum of all radiance samples taken at each pixel
+    sum: Vec<Vec3>,
+    /// statistics of the luminance samples taken at each pixel (including how
+    /// many samples have been taken)
+    stats: Vec<RunningStats>,
+    /// index of the first sample of the next pass
+    next_sample: usize,
+}
+
+impl Accumulator {
+    /// Create a new (empty) accumulator
+    pub fn new(width: usize, height: usize) -> Accumulator {
+        Accumulator {
+            width,
+            height,
+            sum: vec![Vec3::new(0., 0., 0.); width * height],
+            stats: vec![RunningStats::default(); width * height],
+            next_sample: 0,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Throw away all accumulated samples
+    pub fn reset(&mut self) {
+        self.sum.iter_mut().for_each(|s| *s = Vec3::new(0., 0., 0.));
+        self.stats
+            .iter_mut()
+            .for_each(|s| *s = RunningStats::default());
+        self.next_sample = 0;
+    }
+
+    /// Begin a new pass of `samples` samples per-pixel, returning the index of
+    /// the pass's first sample (which is used to pick fresh random numbers for
+    /// the pass).
+    pub fn start_pass(&mut self, samples: usize) -> usize {
+        let first_sample = self.next_sample;
+        self.next_sample += samples;
+        first_sample
+    }
+
+    /// Number of samples per-pixel the image will have once all the passes
+    /// which have been started finish (when sampling adaptively, this is an
+    /// upper bound).
+    pub fn samples(&self) -> usize {
+        self.next_sample
+    }
+
+    /// Add samples (whose radiance adds up to `sum`, and whose luminance is
+    /// described by `stats`) to pixel `i`
+    #[inline]
+    pub fn add(&mut self, i: usize, sum: Vec3, stats: &RunningStats) {
+        self.sum[i] += sum;
+        self.stats[i] = self.stats[i].merge(stats);
+    }
+
+    /// Returns the luminance statistics of each pixel
+    pub fn stats(&self) -> &[RunningStats] {
+        &self.stats
+    }
+
+    /// Iterate over the average radiance at each pixel (black, if the pixel
+    /// hasn't been sampled yet)
+    pub fn pixels(&self) -> impl Iterator<Item = Vec3> + '_ {
+        self.sum.iter().zip(self.stats.iter()).map(|(&sum, stats)| {
+            if stats.n == 0 {
+                Vec3::new(0., 0., 0.)
+            } else {
+                sum / stats.n as f32
+            }
+        })
+    }
+
+    /// Returns the average radiance at each pixel
+    pub fn radiance(&self) -> Vec<Vec3> {
+        self.pixels().collect()
+    }
+
+    /// Tone map and quantize the accumulated image into a 0RGB buffer
+    pub fn resolve_to_buffer(&self, buffer: &mut [u32], display: &DisplayOpts) {
+        display.resolve_to_buffer(self.pixels(), buffer)
+    }
+
+    /// Returns the number of samples taken at each pixel (copied across all
+    /// three channels)
+    pub fn sample_counts(&self) -> Vec<Vec3> {
+        self.stats
+            .iter()
+            .map(|s| Vec3::new(s.n as f32, s.n as f32, s.n as f32))
+            .collect()
+    }
+
+    /// Visualize the number of samples taken at each pixel as a heatmap (in a
+    /// 0RGB buffer), relative to the most-sampled pixel
+    pub fn resolve_heatmap_to_buffer(&self, buffer: &mut [u32]) {
+        let max = self.stats.iter().map(|s| s.n).max().unwrap_or(0).max(1);
+        for (px, s) in buffer.iter_mut().zip(&self.stats) {
+            *px = heat_color(s.n as f32 / max as f32).as_color();
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Adaptive sampling: deciding when a pixel has taken enough samples.
+
+use crate::vec3::Vec3;
+
+use super::RenderOpts;
+
+/// Luminance below which noise is measured in absolute (rather than
+/// relative) terms, so that (nearly) black pixels can still converge.
+const MIN_LUMINANCE: f32 = 0.01;
+
+/// Running mean and variance of a pixel's luminance samples (computed using
+/// Welford's algorithm, which stays accurate over many samples)
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RunningStats {
+    /// number of samples
+    pub n: usize,
+    mean: f32,
+    /// sum of squared differences from the mean
+    m2: f32,
+}
+
+impl RunningStats {
+    /// Add a sample
+    #[inline]
+    pub fn push(&mut self, x: f32) {
+        self.n += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.n as f32;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    /// Combine the statistics of two disjoint sets of samples
+    #[inline]
+    pub fn merge(&self, other: &RunningStats) -> RunningStats {
+        if other.n == 0 {
+            return *self;
+        }
+        if self.n == 0 {
+            return *other;
+        }
+        let n = self.n + other.n;
+        let delta = other.mean - self.mean;
+        RunningStats {
+            n,
+            mean: self.mean + delta * other.n as f32 / n as f32,
+            m2: self.m2 + other.m2 + delta * delta * (self.n * other.n) as f32 / n as f32,
+        }
+    }
+
+    /// Standard error of the mean, relative to the mean
+    pub fn relative_error(&self) -> f32 {
+        if self.n < 2 {
+            return f32::INFINITY;
+        }
+        let variance = self.m2 / (self.n - 1) as f32;
+        (variance / self.n as f32).sqrt() / self.mean.max(MIN_LUMINANCE)
+    }
+
+    /// Check if a pixel with these statistics should stop taking samples
+    #[inline]
+    pub fn converged(&self, opts: &RenderOpts) -> bool {
+        match opts.noise_threshold {
+            None => false,
+            Some(threshold) => {
+                self.n >= opts.max_samples
+                    || (self.n >= opts.min_samples && self.relative_error() < threshold)
+            }
+        }
+    }
+}
+
+/// Map `t` in [0, 1] to a color ramping from black, through purple and orange,
+/// to pale yellow.
+pub(super) fn heat_color(t: f32) -> Vec3 {
+    const STOPS: [(f32, f32, f32); 5] = [
+        (0.0, 0.0, 0.0),
+        (0.3, 0.0, 0.5),
+        (0.8, 0.2, 0.3),
+        (1.0, 0.6, 0.0),
+        (1.0, 1.0, 0.7),
+    ];
+
+    let t = t.clamp(0., 1.) * (STOPS.len() - 1) as f32;
+    let i = (t as usize).min(STOPS.len() - 2);
+    let f = t - i as f32;
+    let (a, b) = (STOPS[i], STOPS[i + 1]);
+    Vec3::new(
+        a.0 + (b.0 - a.0) * f,
+        a.1 + (b.1 - a.1) * f,
+        a.2 + (b.2 - a.2) * f,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::TileOrder;
+    use crate::sampler::SamplerKind;
+
+    fn stats(samples: &[f32]) -> RunningStats {
+        let mut stats = RunningStats::default();
+        samples.iter().for_each(|&x| stats.push(x));
+        stats
+    }
+
+    fn opts(noise_threshold: Option<f32>) -> RenderOpts {
+        RenderOpts {
+            width: 1,
+            height: 1,
+            samples: 4,
+            threads: 1,
+            max_depth: 50,
+            seed: 0,
+            rr_depth: 5,
+            tile_size: 16,
+            tile_order: TileOrder::Scanline,
+            noise_threshold,
+            min_samples: 4,
+            max_samples: 16,
+            sampler: SamplerKind::Independent,
+        }
+    }
+
+    #[test]
+    fn push_computes_mean_and_variance() {
+        let s = stats(&[2., 4., 4., 4., 5., 5., 7., 9.]);
+        assert_eq!(s.n, 8);
+        assert!((s.mean - 5.).abs() < 1e-6);
+        // (sample variance: 32 / 7)
+        assert!((s.m2 - 32.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn merge_matches_pushing_everything() {
+        let samples = [0.5, 1.5, 0.25, 3., 2., 0.75, 1.];
+        let all = stats(&samples);
+        for split in 0..=samples.len() {
+            let (a, b) = samples.split_at(split);
+            let merged = stats(a).merge(&stats(b));
+            assert_eq!(merged.n, all.n);
+            assert!((merged.mean - all.mean).abs() < 1e-5);
+            assert!((merged.m2 - all.m2).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn converged() {
+        let flat = stats(&[1.; 8]);
+        let noisy = stats(&[0., 2., 0., 2., 0., 2., 0., 2.]);
+
+        // never converged without adaptive sampling
+        assert!(!flat.converged(&opts(None)));
+
+        let opts = opts(Some(0.05));
+        assert!(flat.converged(&opts));
+        assert!(!noisy.converged(&opts));
+        // too few samples to tell
+        assert!(!stats(&[1.; 2]).converged(&opts));
+        // out of samples
+        assert!(noisy.merge(&noisy).converged(&opts));
+    }
+}
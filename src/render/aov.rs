@@ -0,0 +1,298 @@
+//! Arbitrary Output Variables, i.e: auxiliary images describing what the
+//! camera sees (as opposed to how it's lit).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::thread;
+
+use rand::Rng;
+
+use crate::hittable::Hittable;
+use crate::material::{Material, MaterialT};
+use crate::scenes::Scene;
+use crate::util::sample_rng;
+use crate::vec3::Vec3;
+
+use super::tonemap::srgb_encode;
+use super::{AsColorExt, RenderOpts};
+
+/// The various AOVs that can be rendered
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Aov {
+    /// distance from the camera to the hit point
+    Depth,
+    /// (world space) surface normal
+    Normal,
+    /// (world space) hit point
+    Position,
+    /// material base color
+    Albedo,
+    /// unique id for each material in the world
+    MaterialId,
+    /// unique id for each object in the world (numbered in the order the
+    /// objects were added to the world)
+    ObjectId,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 6] = [
+        Aov::Depth,
+        Aov::Normal,
+        Aov::Position,
+        Aov::Albedo,
+        Aov::MaterialId,
+        Aov::ObjectId,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+            Aov::Position => "position",
+            Aov::Albedo => "albedo",
+            Aov::MaterialId => "material-id",
+            Aov::ObjectId => "object-id",
+        }
+    }
+}
+
+impl fmt::Display for Aov {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Aov {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Aov, String> {
+        Aov::ALL
+            .iter()
+            .copied()
+            .find(|a| a.name() == s)
+            .ok_or_else(|| {
+                let names = Aov::ALL.iter().map(|a| a.name()).collect::<Vec<_>>();
+                format!(
+                    "unknown AOV '{}' (expected one of: {})",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Per-pixel AOV data (pixels where the camera ray escapes the world are all
+/// zeros).
+#[derive(Debug)]
+pub struct AovBuffers {
+    pub(super) width: usize,
+    pub(super) height: usize,
+    pub(super) depth: Vec<f32>,
+    pub(super) normal: Vec<Vec3>,
+    pub(super) position: Vec<Vec3>,
+    pub(super) albedo: Vec<Vec3>,
+    /// ids start at 1 (0 means "nothing was hit")
+    material_id: Vec<u32>,
+    /// ids start at 1 (0 means "nothing was hit")
+    object_id: Vec<u32>,
+}
+
+/// What the camera ray through a single pixel hit
+struct AovSample<'a> {
+    depth: f32,
+    normal: Vec3,
+    position: Vec3,
+    albedo: Vec3,
+    material: &'a MaterialT,
+    object: usize,
+}
+
+/// Trace a single camera ray through the center of pixel (`x`, `y`)
+fn aov_sample<'a>(
+    x: usize,
+    y: usize,
+    opts: &RenderOpts,
+    scene: &'a impl Scene,
+) -> Option<AovSample<'a>> {
+    let rng = &mut sample_rng(opts.seed, x, y, 0);
+    let u = (x as f32 + 0.5) / opts.width as f32;
+    let v = ((opts.height - y) as f32 + 0.5) / opts.height as f32;
+    let r = scene.get_camera().get_ray((rng.gen(), rng.gen()), u, v);
+
+    let rec = scene.get_world().hit(&r, 0.001..f32::MAX)?;
+    Some(AovSample {
+        depth: rec.t * r.direction.length(),
+        normal: rec.normal,
+        position: rec.p,
+        albedo: rec.material.albedo(&rec),
+        material: rec.material,
+        object: rec.object,
+    })
+}
+
+impl AovBuffers {
+    /// Render all the AOVs, by tracing a single camera ray through each
+    /// pixel (split across `opts.threads` threads).
+    pub fn render(scene: &impl Scene, opts: &RenderOpts) -> AovBuffers {
+        let (width, height) = (opts.width, opts.height);
+        let mut samples: Vec<Option<AovSample>> = Vec::new();
+        samples.resize_with(width * height, || None);
+
+        let chunk_len = (width * height.div_ceil(opts.threads)).max(1);
+        thread::scope(|s| {
+            for (i, chunk) in samples.chunks_mut(chunk_len).enumerate() {
+                s.spawn(move || {
+                    for (j, sample) in chunk.iter_mut().enumerate() {
+                        let px = i * chunk_len + j;
+                        *sample = aov_sample(px % width, px / width, opts, scene);
+                    }
+                });
+            }
+        });
+
+        // Number materials in the order they first appear on screen (which
+        // keeps ids stable between runs). Objects share an id if they share
+        // the same material (e.g: a mesh's faces).
+        let mut material_ids = HashMap::new();
+        let mut material_id = |m: &MaterialT| {
+            let next_id = material_ids.len() as u32 + 1;
+            *material_ids.entry(m as *const MaterialT).or_insert(next_id)
+        };
+
+        let mut aovs = AovBuffers {
+            width,
+            height,
+            depth: Vec::with_capacity(width * height),
+            normal: Vec::with_capacity(width * height),
+            position: Vec::with_capacity(width * height),
+            albedo: Vec::with_capacity(width * height),
+            material_id: Vec::with_capacity(width * height),
+            object_id: Vec::with_capacity(width * height),
+        };
+
+        let zero = Vec3::new(0., 0., 0.);
+        for sample in samples {
+            match sample {
+                Some(s) => {
+                    aovs.depth.push(s.depth);
+                    aovs.normal.push(s.normal);
+                    aovs.position.push(s.position);
+                    aovs.albedo.push(s.albedo);
+                    aovs.material_id.push(material_id(s.material));
+                    aovs.object_id.push(s.object as u32 + 1);
+                }
+                None => {
+                    aovs.depth.push(0.);
+                    aovs.normal.push(zero);
+                    aovs.position.push(zero);
+                    aovs.albedo.push(zero);
+                    aovs.material_id.push(0);
+                    aovs.object_id.push(0);
+                }
+            }
+        }
+
+        aovs
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the raw values of an AOV (scalar AOVs are copied across all
+    /// three channels)
+    pub fn raw(&self, aov: Aov) -> Vec<Vec3> {
+        let splat = |x: f32| Vec3::new(x, x, x);
+        match aov {
+            Aov::Depth => self.depth.iter().map(|&d| splat(d)).collect(),
+            Aov::Normal => self.normal.clone(),
+            Aov::Position => self.position.clone(),
+            Aov::Albedo => self.albedo.clone(),
+            Aov::MaterialId => self.material_id.iter().map(|&i| splat(i as f32)).collect(),
+            Aov::ObjectId => self.object_id.iter().map(|&i| splat(i as f32)).collect(),
+        }
+    }
+
+    /// Visualize an AOV as a 0RGB buffer
+    pub fn resolve_to_buffer(&self, aov: Aov, buffer: &mut [u32]) {
+        let colors: Vec<Vec3> = match aov {
+            Aov::Depth => {
+                // near is white, far fades to black (with the median depth
+                // mapping to 50% gray). Normalizing by the max depth instead
+                // would let far-off grazing hits wash everything out.
+                let mut hits = self.depth.iter().filter(|&&d| d > 0.).collect::<Vec<_>>();
+                hits.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let median = hits.get(hits.len() / 2).map_or(1., |&&d| d);
+                self.depth
+                    .iter()
+                    .map(|&d| {
+                        let x = if d > 0. { median / (median + d) } else { 0. };
+                        Vec3::new(x, x, x)
+                    })
+                    .collect()
+            }
+            Aov::Normal => self
+                .normal
+                .iter()
+                .zip(&self.depth)
+                .map(|(n, &d)| {
+                    if d > 0. {
+                        0.5 * (*n + Vec3::new(1., 1., 1.))
+                    } else {
+                        *n
+                    }
+                })
+                .collect(),
+            Aov::Position => {
+                // normalize to the bounds of the visible hit points
+                let hits = || {
+                    self.position
+                        .iter()
+                        .zip(&self.depth)
+                        .filter(|(_, &d)| d > 0.)
+                };
+                let inf = Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+                let min = hits().fold(inf, |m, (p, _)| m.min(p));
+                let max = hits().fold(-inf, |m, (p, _)| m.max(p));
+                let extent = (max - min).max(&Vec3::new(1e-6, 1e-6, 1e-6));
+                self.position
+                    .iter()
+                    .zip(&self.depth)
+                    .map(|(&p, &d)| if d > 0. { (p - min) / extent } else { p })
+                    .collect()
+            }
+            Aov::Albedo => self
+                .albedo
+                .iter()
+                .map(|c| {
+                    let c = c.min(&Vec3::new(1., 1., 1.));
+                    Vec3::new(srgb_encode(c.x), srgb_encode(c.y), srgb_encode(c.z))
+                })
+                .collect(),
+            Aov::MaterialId => self.material_id.iter().map(|&i| id_color(i)).collect(),
+            Aov::ObjectId => self.object_id.iter().map(|&i| id_color(i)).collect(),
+        };
+
+        for (px, c) in buffer.iter_mut().zip(colors) {
+            *px = c.as_color();
+        }
+    }
+}
+
+/// Pick a (pseudo-random, but consistent) color to represent an id
+fn id_color(id: u32) -> Vec3 {
+    if id == 0 {
+        return Vec3::new(0., 0., 0.);
+    }
+    let mut rng = sample_rng(0, id as usize, 0, 0);
+    Vec3::new(
+        rng.gen_range(0.2, 1.),
+        rng.gen_range(0.2, 1.),
+        rng.gen_range(0.2, 1.),
+    )
+}
@@ -1,16 +1,16 @@
 use crate::scenes::Scene;
 
-use super::{render_pixel, RenderOpts};
+use super::{render_pixel, Accumulator, RenderOpts};
 
-/// Synchronously render scene into buffer
-pub fn trace_some_rays_blocking(buffer: &mut Vec<u32>, scene: &impl Scene, opts: RenderOpts) {
-    buffer
-        .chunks_mut(opts.width)
-        .enumerate()
-        .for_each(|(y, row)| {
-            row.iter_mut().enumerate().for_each(|(x, px)| {
-                let mut rng = rand::thread_rng();
-                *px = render_pixel(&mut rng, x, y, &opts, scene);
-            })
-        });
+/// Synchronously render a pass over the scene, adding it to the accumulator
+pub fn trace_some_rays_blocking(accum: &mut Accumulator, scene: &impl Scene, opts: RenderOpts) {
+    let first_sample = accum.start_pass(opts.samples);
+    for y in 0..opts.height {
+        for x in 0..opts.width {
+            let i = y * opts.width + x;
+            let prior = accum.stats()[i];
+            let (sum, stats) = render_pixel(x, y, first_sample, &prior, &opts, scene);
+            accum.add(i, sum, &stats);
+        }
+    }
 }
@@ -0,0 +1,140 @@
+//! Edge-avoiding À-Trous wavelet denoising, guided by the AOV feature buffers.
+//!
+//! See "Edge-Avoiding À-Trous Wavelet Transform for fast Global Illumination
+//! Filtering" (Dammertz et al. 2010).
+
+use std::thread;
+
+use crate::vec3::Vec3;
+
+use super::AovBuffers;
+
+/// Number of filter passes (each doubling the filter's footprint)
+const ITERATIONS: usize = 5;
+/// B3 spline kernel
+const KERNEL: [f32; 5] = [1. / 16., 1. / 4., 3. / 8., 1. / 4., 1. / 16.];
+
+/// How quickly weights fall off as the guide buffers diverge
+const SIGMA_COLOR: f32 = 0.6;
+const SIGMA_NORMAL: f32 = 0.3;
+/// (relative to the depth of the pixel being filtered)
+const SIGMA_POSITION: f32 = 0.05;
+
+/// Smallest albedo component that's divided out before filtering
+const MIN_ALBEDO: f32 = 0.01;
+
+/// Denoise a (linear) image using the AOVs rendered for the same frame,
+/// splitting the work across `threads` threads.
+///
+/// Lighting is filtered separately from surface color (by dividing out the
+/// albedo beforehand), which keeps texture detail sharp.
+pub fn denoise(color: &[Vec3], aovs: &AovBuffers, threads: usize) -> Vec<Vec3> {
+    let (width, height) = (aovs.width, aovs.height);
+    assert_eq!(color.len(), width * height);
+
+    let demodulate = |a: f32| if a > MIN_ALBEDO { a } else { 1. };
+    let albedo = aovs
+        .albedo
+        .iter()
+        .map(|a| Vec3::new(demodulate(a.x), demodulate(a.y), demodulate(a.z)))
+        .collect::<Vec<_>>();
+
+    let mut src = color
+        .iter()
+        .zip(&albedo)
+        .map(|(&c, &a)| c / a)
+        .collect::<Vec<_>>();
+    let mut dst = vec![Vec3::new(0., 0., 0.); width * height];
+
+    for i in 0..ITERATIONS {
+        let pass = Pass {
+            src: &src,
+            aovs,
+            step: 1 << i,
+            // later passes average over larger areas, and should be more
+            // careful about what they average
+            sigma_color: SIGMA_COLOR / (1 << i) as f32,
+        };
+
+        let chunk_len = (width * height.div_ceil(threads)).max(1);
+        thread::scope(|s| {
+            for (n, chunk) in dst.chunks_mut(chunk_len).enumerate() {
+                let pass = &pass;
+                s.spawn(move || {
+                    for (j, px) in chunk.iter_mut().enumerate() {
+                        *px = pass.filter(n * chunk_len + j);
+                    }
+                });
+            }
+        });
+
+        std::mem::swap(&mut src, &mut dst);
+    }
+
+    src.iter().zip(&albedo).map(|(&c, &a)| c * a).collect()
+}
+
+/// A single À-Trous filter pass
+struct Pass<'a> {
+    src: &'a [Vec3],
+    aovs: &'a AovBuffers,
+    step: usize,
+    sigma_color: f32,
+}
+
+impl Pass<'_> {
+    /// Filter pixel `i`
+    fn filter(&self, i: usize) -> Vec3 {
+        let (width, height) = (self.aovs.width, self.aovs.height);
+        let (x, y) = ((i % width) as isize, (i / width) as isize);
+
+        let c_p = self.src[i];
+        let n_p = self.aovs.normal[i];
+        let x_p = self.aovs.position[i];
+        let d_p = self.aovs.depth[i];
+
+        let mut sum = Vec3::new(0., 0., 0.);
+        let mut weight_sum = 0.;
+        for (ky, hy) in KERNEL.iter().enumerate() {
+            let qy = y + (ky as isize - 2) * self.step as isize;
+            if qy < 0 || qy >= height as isize {
+                continue;
+            }
+            for (kx, hx) in KERNEL.iter().enumerate() {
+                let qx = x + (kx as isize - 2) * self.step as isize;
+                if qx < 0 || qx >= width as isize {
+                    continue;
+                }
+                let q = qy as usize * width + qx as usize;
+
+                // never blend surfaces with the background
+                let d_q = self.aovs.depth[q];
+                if (d_p > 0.) != (d_q > 0.) {
+                    continue;
+                }
+
+                let c_q = self.src[q];
+                let w_color = (-(c_p - c_q).squared_length() / self.sigma_color.powi(2)).exp();
+
+                let w_normal = {
+                    let dist = (n_p - self.aovs.normal[q]).squared_length();
+                    (-dist / SIGMA_NORMAL.powi(2)).exp()
+                };
+
+                let w_position = if d_p > 0. {
+                    let dist = (x_p - self.aovs.position[q]).squared_length();
+                    (-dist / (SIGMA_POSITION * d_p).powi(2)).exp()
+                } else {
+                    1.
+                };
+
+                let w = hx * hy * w_color * w_normal * w_position;
+                sum += w * c_q;
+                weight_sum += w;
+            }
+        }
+
+        // the center pixel always has a non-zero weight
+        sum / weight_sum
+    }
+}
@@ -1,15 +1,32 @@
-use rand::Rng;
-
+mod accumulator;
+mod adaptive;
+mod aov;
 mod blocking;
+mod denoise;
 mod nonblocking;
+mod stats;
+mod tiles;
+mod tonemap;
 
+pub use accumulator::Accumulator;
+pub use adaptive::RunningStats;
+pub use aov::{Aov, AovBuffers};
 pub use blocking::trace_some_rays_blocking;
-pub use nonblocking::{trace_some_rays_nonblocking, RenderProgress};
+pub use denoise::denoise;
+pub use nonblocking::{RenderError, RenderProgress, Renderer};
+pub use stats::RenderStats;
+pub use tiles::TileOrder;
+pub use tonemap::{DisplayOpts, Tonemap};
 
-use crate::hittable::Hittable;
+use crate::counters::count_ray;
+use crate::environment::Environment;
+use crate::hittable::{HitRecord, Hittable};
+use crate::light::{Light, LightSet};
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::sampler::{dim, Halton, Independent, Sampler, SamplerKind, Sobol, Stratified};
 use crate::scenes::Scene;
+use crate::util::luminance;
 use crate::vec3::Vec3;
 
 /// Container for various render options
@@ -21,65 +38,260 @@ pub struct RenderOpts {
     pub height: usize,
     /// samples per-pixel
     pub samples: usize,
+    /// number of threads to render with
+    pub threads: usize,
+    /// maximum number of bounces per ray
+    pub max_depth: usize,
+    /// seed for all the random numbers used while rendering. Renders using
+    /// the same seed (and options) are identical.
+    pub seed: u64,
+    /// number of bounces after which paths may be terminated via Russian
+    /// roulette
+    pub rr_depth: usize,
+    /// width and height of the tiles the image is split into (only used by
+    /// the nonblocking renderer)
+    pub tile_size: usize,
+    /// order in which tiles are rendered (only used by the nonblocking
+    /// renderer)
+    pub tile_order: TileOrder,
+    /// enables adaptive sampling: pixels stop taking samples once the
+    /// relative standard error of their luminance drops below this threshold
+    pub noise_threshold: Option<f32>,
+    /// (adaptive sampling) samples a pixel must take before it may be
+    /// considered converged
+    pub min_samples: usize,
+    /// (adaptive sampling) maximum number of samples a pixel may accumulate
+    /// across all passes
+    pub max_samples: usize,
+    /// generator of the numbers used to make each sample
+    pub sampler: SamplerKind,
 }
 
-const MAX_DEPTH: usize = 50;
-
 /// Core ray-tracing method.
-fn color(r: &Ray, world: &impl Hittable, depth: usize) -> Vec3 {
-    if let Some(rec) = world.hit(r, 0.001..std::f32::MAX) {
-        if depth >= MAX_DEPTH {
-            return Vec3::new(0.0, 0.0, 0.0);
+///
+/// Iteratively follows a path through the scene, accumulating emitted light
+/// weighted by the path's throughput. At each bounce off of a surface that
+/// isn't perfectly specular, one of the scene's lights is also sampled
+/// directly (with a shadow ray). Light reaching the path both ways (i.e: from
+/// sampling a light, and from scattering off of the surface and hitting a
+/// light) is combined using multiple importance sampling.
+///
+/// Once a path has bounced `opts.rr_depth` times, it is terminated using
+/// Russian roulette (with survivors weighted to compensate, keeping the
+/// estimate unbiased).
+fn color(sampler: &mut impl Sampler, r: &Ray, scene: &impl Scene, opts: &RenderOpts) -> Vec3 {
+    let world = scene.get_world();
+    let lights = scene.get_lights();
+
+    let mut radiance = Vec3::new(0., 0., 0.);
+    let mut throughput = Vec3::new(1., 1., 1.);
+    let mut ray = *r;
+    // pdf with which the previous bounce picked the current ray's direction,
+    // if lights were also sampled directly at that bounce
+    let mut scatter_pdf = None;
+
+    for depth in 0..=opts.max_depth {
+        count_ray(depth);
+        let rec = match world.hit(&ray, 0.001..f32::MAX) {
+            Some(rec) => rec,
+            None => {
+                radiance += throughput * scene.get_environment().color(&ray.direction);
+                break;
+            }
+        };
+
+        let emitted = rec.material.emitted(&rec);
+        if emitted.squared_length() > 0. {
+            // only the light matching the hit primitive could have sampled
+            // this direction (lights behind it are occluded)
+            let weight = match (scatter_pdf, rec.light) {
+                (Some(pdf), Some(index)) => {
+                    let dir = ray.direction.normalize();
+                    power_heuristic(pdf, lights.pdf(index, &ray.origin, &dir))
+                }
+                _ => 1.,
+            };
+            radiance += throughput * emitted * weight;
+        }
+        if depth == opts.max_depth {
+            break;
+        }
+
+        if !lights.is_empty() {
+            sampler.set_dimension(dim::bounce(depth) + dim::LIGHT);
+            radiance += throughput * sample_lights(sampler, &ray, &rec, world, lights, depth);
         }
 
-        if let Some((attenuation, scattered)) = rec.material.scatter(r, &rec) {
-            return attenuation * color(&scattered, world, depth + 1);
+        sampler.set_dimension(dim::bounce(depth) + dim::SCATTER);
+        let (attenuation, scattered) = match rec.material.scatter(sampler, &ray, &rec) {
+            Some(x) => x,
+            None => break,
+        };
+        throughput *= attenuation;
+
+        // lights were sampled (successfully or not) iff the material can be
+        // evaluated, i.e: it isn't perfectly specular
+        scatter_pdf = if !lights.is_empty() {
+            let dir = scattered.direction.normalize();
+            rec.material.eval(&ray, &rec, &dir).map(|(_, pdf)| pdf)
+        } else {
+            None
+        };
+
+        if depth >= opts.rr_depth {
+            // paths that can't contribute much are likely to be terminated
+            let p = throughput.x.max(throughput.y).max(throughput.z).min(0.95);
+            sampler.set_dimension(dim::bounce(depth) + dim::RUSSIAN_ROULETTE);
+            if sampler.next_1d() >= p {
+                break;
+            }
+            throughput /= p;
         }
 
-        return Vec3::new(0.0, 0.0, 0.0);
+        ray = scattered;
+    }
+
+    radiance
+}
+
+/// Estimates the light arriving at hit `rec` directly from one of `lights`
+/// (picked at random, in proportion to their power), and scattered back along
+/// `r_in`. The estimate is weighted for combination with the light found by
+/// scattering off of the surface.
+///
+/// Returns black if the hit material can't be lit by sampling lights directly
+/// (i.e: it's perfectly specular), or if the light couldn't be sampled.
+fn sample_lights(
+    sampler: &mut impl Sampler,
+    r_in: &Ray,
+    rec: &HitRecord,
+    world: &impl Hittable,
+    lights: &LightSet,
+    depth: usize,
+) -> Vec3 {
+    let black = Vec3::new(0., 0., 0.);
+
+    let (light, prob) = lights.pick(sampler.next_1d());
+    let sample = match light.sample(&rec.p, sampler.next_2d()) {
+        Some(sample) => sample,
+        None => return black,
+    };
+    let (f, scatter_pdf) = match rec.material.eval(r_in, rec, &sample.dir) {
+        Some(x) => x,
+        None => return black,
+    };
+    if f.squared_length() == 0. {
+        return black;
+    }
+
+    count_ray(depth + 1);
+    let shadow = Ray::new(rec.p, sample.dir);
+    if world.occluded(&shadow, 0.001..sample.dist * 0.999) {
+        return black;
     }
 
-    // Background gradient
-    let unit_direction = r.direction.normalize();
-    let t = 0.5 * (unit_direction.y + 1.0);
-    (1.0 - t) * Vec3::new(1.0, 1.0, 1.0) + t * Vec3::new(0.5, 0.7, 1.0)
+    let pdf = sample.pdf * prob;
+    // scattered rays can't find lights outside the world, so there's nothing
+    // to combine with
+    let weight = if light.hittable() {
+        power_heuristic(pdf, scatter_pdf)
+    } else {
+        1.
+    };
+    f * sample.radiance * (weight / pdf)
+}
+
+/// Multiple importance sampling weight for a sample taken with probability
+/// density `pdf`, which could also have been taken by another technique with
+/// probability density `other_pdf`.
+///
+/// See "Optimally Combining Sampling Techniques for Monte Carlo Rendering"
+/// (Veach and Guibas 1995)
+fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b > 0. {
+        a / (a + b)
+    } else {
+        0.
+    }
 }
 
 /// Core per-pixel render method.
 /// Shared between various buffer drivers.
+///
+/// Takes up to `opts.samples` samples (starting from sample number
+/// `first_sample`), and returns the sum of their radiance, along with the
+/// statistics of their luminance. When sampling adaptively, sampling stops
+/// early once the pixel (whose earlier samples are described by `prior`) has
+/// converged.
 #[inline]
 fn render_pixel(
-    rng: &mut impl Rng,
     x: usize,
     y: usize,
+    first_sample: usize,
+    prior: &RunningStats,
+    opts: &RenderOpts,
+    scene: &impl Scene,
+) -> (Vec3, RunningStats) {
+    macro_rules! samples {
+        ($sampler:expr) => {
+            take_samples(&mut $sampler, x, y, first_sample, prior, opts, scene)
+        };
+    }
+    match opts.sampler {
+        SamplerKind::Independent => samples!(Independent::new(opts.seed)),
+        SamplerKind::Stratified => samples!(Stratified::new(opts.seed, opts.samples)),
+        SamplerKind::Halton => samples!(Halton::new(opts.seed)),
+        SamplerKind::Sobol => samples!(Sobol::new(opts.seed)),
+    }
+}
+
+/// [render_pixel], using a particular sampler
+#[inline]
+fn take_samples(
+    sampler: &mut impl Sampler,
+    x: usize,
+    y: usize,
+    first_sample: usize,
+    prior: &RunningStats,
     opts: &RenderOpts,
     scene: &impl Scene,
-) -> u32 {
-    let y = (opts.height - y) as f32;
-    let x = x as f32;
+) -> (Vec3, RunningStats) {
+    let mut sum = Vec3::new(0.0, 0.0, 0.0);
+    let mut stats = RunningStats::default();
+
+    for sample in first_sample..first_sample + opts.samples {
+        if prior.merge(&stats).converged(opts) {
+            break;
+        }
 
-    let avg_color = (0..opts.samples).fold(Vec3::new(0.0, 0.0, 0.0), |col, _| {
-        let u = (x + rng.gen::<f32>()) / opts.width as f32;
-        let v = (y + rng.gen::<f32>()) / opts.height as f32;
+        sampler.start_sample(x, y, sample);
 
-        let r = scene.get_camera().get_ray(u, v);
+        sampler.set_dimension(dim::PIXEL);
+        let (jx, jy) = sampler.next_2d();
+        let u = (x as f32 + jx) / opts.width as f32;
+        let v = ((opts.height - y) as f32 + jy) / opts.height as f32;
 
-        col + color(&r, scene.get_world(), 0)
-    }) / opts.samples as f32;
+        sampler.set_dimension(dim::LENS);
+        let r = scene.get_camera().get_ray(sampler.next_2d(), u, v);
 
-    let avg_color = Vec3::new(avg_color.x.sqrt(), avg_color.y.sqrt(), avg_color.z.sqrt());
+        let c = color(sampler, &r, scene, opts);
+        sum += c;
+        stats.push(luminance(c));
+    }
 
-    avg_color.as_color()
+    (sum, stats)
 }
 
 /// Extension trait for returning self as a u32 RGBA value.
 trait AsColorExt {
-    fn as_color(self) -> u32;
+    fn as_color(&self) -> u32;
 }
 
 impl AsColorExt for Vec3 {
-    fn as_color(mut self) -> u32 {
-        self *= 255.99;
-        u32::from_le_bytes([self.z as u8, self.y as u8, self.x as u8, 0])
+    /// Components are expected to be in [0, 1]
+    fn as_color(&self) -> u32 {
+        let c = *self * 255. + Vec3::new(0.5, 0.5, 0.5);
+        u32::from_le_bytes([c.z as u8, c.y as u8, c.x as u8, 0])
     }
 }
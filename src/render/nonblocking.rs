@@ -1,23 +1,30 @@
-use std::ops::Range;
-use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, TryRecvError};
+use std::sync::{Arc, RwLock};
 use std::thread;
+use std::time::{Duration, Instant};
 
+use crate::counters::{take_counts, RayCounts};
 use crate::scenes::Scene;
+use crate::vec3::Vec3;
 
-use super::{render_pixel, RenderOpts};
+use super::adaptive::RunningStats;
+use super::stats::RenderStats;
+use super::tiles::{tiles, Tile};
+use super::{render_pixel, Accumulator, RenderOpts};
 
-/// Specifies the number of pixels to send per job.
-/// There is a sweet-spot for cache utilization, that varies from machine to
-/// machine. That said, setting it to 1 looks cool lol.
-///
-/// Uncomment the #[derive(Debug)] if you want to go past 32
-/// (until const generics land)
-const CHUNK_SIZE: usize = 1;
-
-// #[derive(Debug)]
+/// A rendered tile
 struct RenderChunk {
-    range: Range<usize>,
-    buf: [u32; CHUNK_SIZE],
+    tile: Tile,
+    /// sum of each pixel's radiance samples, along with their luminance
+    /// statistics (row by row)
+    buf: Vec<(Vec3, RunningStats)>,
+    /// id of the thread that rendered the tile
+    thread: usize,
+    /// how long it took to render
+    busy: Duration,
+    rays: RayCounts,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -28,198 +35,448 @@ enum ThreadProgress {
     Done,
     /// Encountered error / termniate signal
     Terminated,
+    /// Exited without reporting back (i.e: the thread panicked)
+    Failed,
+}
+
+/// Error returned when a render thread dies (i.e: panics) mid-frame, leaving
+/// the frame (and the [Renderer]) unable to finish
+#[derive(Debug)]
+pub struct RenderError {
+    /// id of the thread that died
+    pub thread: usize,
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "render thread render-{} died", self.thread)
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+/// A single frame's worth of work, shared between all the workers
+struct Frame {
+    /// Tiles to render, in order
+    tiles: Vec<Tile>,
+    /// Index of the next tile to be picked up by a worker
+    next_tile: AtomicUsize,
+    /// Set when the frame is invalidated (or replaced by a newer frame)
+    cancelled: AtomicBool,
+    first_sample: usize,
+    /// Luminance statistics of each pixel before the frame started (only
+    /// when sampling adaptively)
+    prior: Vec<RunningStats>,
+    opts: RenderOpts,
+}
+
+/// A frame, as sent to an individual worker
+struct Job {
+    frame: Arc<Frame>,
+    progress_tx: mpsc::Sender<RenderChunk>,
+    /// (each worker gets its own status channel, so that a worker dying
+    /// mid-job shows up as its channel disconnecting)
+    thread_tx: mpsc::Sender<ThreadProgress>,
 }
 
 /// Manages current frame being rendered.
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub struct RenderProgress {
     /// true if the frame is aborting early (e.g: render params change)
     invalidated: bool,
-    /// Chunks remaining
-    remaining: usize,
+    /// Tiles rendered so far
+    tiles_done: usize,
+    /// Tiles in the frame
+    tiles_total: usize,
+    /// When the frame was started
+    start: Option<Instant>,
+    /// When the frame was first noticed to be done
+    end: Option<Instant>,
+    /// Samples taken so far
+    samples_taken: u64,
+    /// Rays cast so far
+    rays: RayCounts,
+    /// Time each thread has spent rendering
+    thread_busy: Vec<Duration>,
+    /// The frame being rendered
+    frame: Option<Arc<Frame>>,
     /// Incoming raytracer progress
     // The only reason it's an optional is because you can't easily make a
     // detached mpsc::Reciever
     progress_rx: Option<mpsc::Receiver<RenderChunk>>,
     /// Thread Status
     thread_status: Vec<ThreadProgress>,
-    /// Thread Progress channle
-    thread_rx: Option<mpsc::Receiver<(usize, ThreadProgress)>>,
-    /// Thread Control channle
-    thread_term_tx: Vec<mpsc::Sender<()>>,
+    /// Thread Progress channels (one per thread)
+    thread_rx: Vec<mpsc::Receiver<ThreadProgress>>,
 }
 
 impl RenderProgress {
-    fn new(
-        num_threads: usize,
-        remaining: usize,
-        progress_rx: mpsc::Receiver<RenderChunk>,
-        thread_rx: mpsc::Receiver<(usize, ThreadProgress)>,
-        thread_term_tx: Vec<mpsc::Sender<()>>,
-    ) -> RenderProgress {
-        RenderProgress {
-            invalidated: false,
-            remaining,
-            progress_rx: Some(progress_rx),
-            thread_status: vec![ThreadProgress::Active; num_threads],
-            thread_rx: Some(thread_rx),
-            thread_term_tx,
-        }
-    }
-
-    /// Check if the frame is done rendering
-    pub fn poll_done(&mut self) -> bool {
+    /// Check if the frame is done rendering.
+    ///
+    /// Returns an error if any of the render threads died before finishing
+    /// their work.
+    pub fn poll_done(&mut self) -> Result<bool, RenderError> {
         // Check if the threads sent any status updates
-        if let Some(thread_rx) = &self.thread_rx {
-            for (id, status) in thread_rx.try_iter() {
-                self.thread_status[id] = status;
+        for (status, thread_rx) in self.thread_status.iter_mut().zip(&self.thread_rx) {
+            loop {
+                match thread_rx.try_recv() {
+                    Ok(new_status) => *status = new_status,
+                    Err(TryRecvError::Empty) => break,
+                    // the thread dropped its job without reporting back
+                    Err(TryRecvError::Disconnected) => {
+                        if *status == ThreadProgress::Active {
+                            *status = ThreadProgress::Failed;
+                        }
+                        break;
+                    }
+                }
             }
         }
 
-        // "done" in the sense that no threads are active
-        let any_active = self
+        if let Some(thread) = self
             .thread_status
             .iter()
-            .any(|s| *s == ThreadProgress::Active);
+            .position(|&s| s == ThreadProgress::Failed)
+        {
+            return Err(RenderError { thread });
+        }
+
+        // "done" in the sense that no threads are active
+        let any_active = self.thread_status.contains(&ThreadProgress::Active);
 
-        !any_active || self.invalidated
+        let done = !any_active || self.invalidated;
+        if done && self.end.is_none() {
+            self.end = Some(Instant::now());
+        }
+        Ok(done)
+    }
+
+    /// Returns a snapshot of the frame's progress (as of the last call to
+    /// [RenderProgress::flush_to_buffer])
+    pub fn stats(&self) -> RenderStats {
+        let elapsed = match (self.start, self.end) {
+            (Some(start), Some(end)) => end - start,
+            (Some(start), None) => start.elapsed(),
+            _ => Duration::default(),
+        };
+        RenderStats {
+            tiles_done: self.tiles_done,
+            tiles_total: self.tiles_total,
+            elapsed,
+            samples: self.samples_taken,
+            rays: self.rays,
+            thread_busy: self.thread_busy.clone(),
+        }
     }
 
     /// Invalidate frame, marking it as done
     pub fn invalidate(&mut self) {
         self.invalidated = true;
-        for chan in self.thread_term_tx.iter() {
-            // okay if the message isn't recieved.
-            // that just means that the thread has already finished.
-            let _ = chan.send(());
+        if let Some(frame) = &self.frame {
+            // workers notice this between chunks, and move on to the next job
+            frame.cancelled.store(true, Ordering::Relaxed);
         }
     }
 
-    /// Flush current render state to output buffer
-    pub fn flush_to_buffer(&mut self, buffer: &mut Vec<u32>) {
+    /// Flush current render state to the accumulation buffer
+    pub fn flush_to_buffer(&mut self, accum: &mut Accumulator) {
         if self.invalidated {
             return;
         }
 
         if let Some(progress_rx) = &self.progress_rx {
             for chunk in progress_rx.try_iter() {
-                let offset = chunk.range.start;
-                for buf_i in chunk.range {
-                    buffer[buf_i] = chunk.buf[buf_i - offset]
+                for ((x, y), (sum, stats)) in chunk.tile.pixels().zip(&chunk.buf) {
+                    accum.add(y * accum.width() + x, *sum, stats);
+                    self.samples_taken += stats.n as u64;
                 }
 
-                self.remaining -= 1;
+                self.tiles_done += 1;
+                self.rays += chunk.rays;
+                self.thread_busy[chunk.thread] += chunk.busy;
             }
         }
     }
+
+    /// Block until the frame is done rendering, flushing all progress to the
+    /// accumulation buffer along the way.
+    pub fn wait_to_buffer(&mut self, accum: &mut Accumulator) -> Result<(), RenderError> {
+        while !self.poll_done()? {
+            self.flush_to_buffer(accum);
+            thread::sleep(Duration::from_millis(10));
+        }
+        // pick up any chunks sent just before the threads finished
+        self.flush_to_buffer(accum);
+        Ok(())
+    }
 }
 
-/// Individual render worker thread
-fn render_worker(
-    id: usize,
-    scene: Arc<RwLock<impl Scene>>,
-    work_pool: Arc<Mutex<Vec<Range<usize>>>>,
-    progress_tx: mpsc::Sender<RenderChunk>,
-    thread_tx: mpsc::Sender<(usize, ThreadProgress)>,
-    thread_term_rx: mpsc::Receiver<()>,
-    opts: RenderOpts,
-) {
-    let mut rng = rand::thread_rng();
+/// Individual render worker thread, which renders frames as they come in
+/// until the [Renderer] is dropped.
+fn render_worker(id: usize, scene: Arc<RwLock<impl Scene>>, job_rx: mpsc::Receiver<Job>) {
+    for job in job_rx {
+        let status = render_job(id, &scene, &job);
+        // okay if the message isn't recieved.
+        // that just means that nobody is waiting on the frame anymore.
+        let _ = job.thread_tx.send(status);
+    }
+}
+
+/// Render chunks of a frame until it runs out of work (or is cancelled)
+fn render_job(id: usize, scene: &RwLock<impl Scene>, job: &Job) -> ThreadProgress {
+    let frame = &*job.frame;
+    let opts = &frame.opts;
     let scene = scene.read().unwrap();
 
+    // drop any counts left over from a cancelled frame
+    take_counts();
+
     // work-stealing
     loop {
         // check for early terminate signal
-        if let Ok(()) = thread_term_rx.try_recv() {
-            let _ = thread_tx.send((id, ThreadProgress::Terminated));
-            break;
-        }
-
-        // Get range from work pool
-        let range = {
-            // scope the work access to release the lock ASAP
-            let mut work = work_pool.lock().unwrap();
-            if let Some(range) = work.pop() {
-                range
-            } else {
-                // No more work to do. kill the thread.
-                let _ = thread_tx.send((id, ThreadProgress::Done));
-                break;
-            }
+        if frame.cancelled.load(Ordering::Relaxed) {
+            return ThreadProgress::Terminated;
+        }
+
+        // Grab the next tile
+        let i = frame.next_tile.fetch_add(1, Ordering::Relaxed);
+        let tile = match frame.tiles.get(i) {
+            Some(&tile) => tile,
+            // No more work to do
+            None => return ThreadProgress::Done,
         };
 
         // The actual ray-tracing work
-        let mut buf = [0; CHUNK_SIZE];
-        let offset = range.start;
-        for px_i in range.clone() {
-            let x = px_i % opts.width;
-            let y = px_i / opts.width;
-            buf[px_i - offset] = render_pixel(&mut rng, x, y, &opts, &*scene);
-        }
+        let start = Instant::now();
+        let buf = tile
+            .pixels()
+            .map(|(x, y)| {
+                let prior = frame
+                    .prior
+                    .get(y * opts.width + x)
+                    .copied()
+                    .unwrap_or_default();
+                render_pixel(x, y, frame.first_sample, &prior, opts, &*scene)
+            })
+            .collect::<Vec<_>>();
 
         // Ship off the completed buffer
-        match progress_tx.send(RenderChunk { range, buf }) {
-            Ok(_) => {}
-            Err(_) => {
-                let _ = thread_tx.send((id, ThreadProgress::Terminated));
-                break;
-            }
+        let chunk = RenderChunk {
+            tile,
+            buf,
+            thread: id,
+            busy: start.elapsed(),
+            rays: take_counts(),
+        };
+        if job.progress_tx.send(chunk).is_err() {
+            return ThreadProgress::Terminated;
         }
     }
 }
 
-/// Render a scene without blocking the main thread.
-/// Returns a [RenderProgress] which can be polled for the status of the frame
-/// that's currently beign rendered.
-pub fn trace_some_rays_nonblocking(
-    scene: &Arc<RwLock<impl Scene + 'static>>,
-    opts: RenderOpts,
-) -> RenderProgress {
-    let scene = Arc::clone(scene);
-
-    // Generate work pool
-    let mut work_pool = (0usize..opts.width * opts.height)
-        .step_by(CHUNK_SIZE)
-        .map(|start| start..(std::cmp::min(start + CHUNK_SIZE, opts.width * opts.height)))
-        .collect::<Vec<_>>();
-
-    // Shuffle the work pool, since it looks cooler
-    use rand::seq::SliceRandom;
-    work_pool.shuffle(&mut rand::thread_rng());
-
-    // Wrap it up in a mutex
-    let remaining = work_pool.len();
-    let work = Arc::new(Mutex::new(work_pool));
-
-    // create the progress channel
-    let (progress_tx, progress_rx) = mpsc::channel();
-    // create the thread status channe
-    let (thread_tx, thread_rx) = mpsc::channel();
-
-    // spin up the worker threads
-    let mut thread_term_tx = Vec::new();
-    for id in 0..num_cpus::get() {
-        // clone all those Arc pointers
-        let scene = Arc::clone(&scene);
-        let work = Arc::clone(&work);
-        let progress_tx = progress_tx.clone();
-        let thread_tx = thread_tx.clone();
-
-        // create a terminate signal channel for the thread
-        let (term_tx, term_rx) = mpsc::channel();
-        thread_term_tx.push(term_tx);
-
-        // spin off the individual worker threads
-        thread::spawn(move || {
-            render_worker(id, scene, work, progress_tx, thread_tx, term_rx, opts)
+/// A pool of long-lived render threads, which render passes over a scene
+/// without blocking the main thread.
+///
+/// Only a single frame is rendered at a time: starting a new frame cancels
+/// whatever frame was being rendered before it.
+pub struct Renderer {
+    /// Job channels for each worker
+    job_tx: Vec<mpsc::Sender<Job>>,
+    workers: Vec<thread::JoinHandle<()>>,
+    /// The most recently started frame
+    current: Option<Arc<Frame>>,
+}
+
+impl Renderer {
+    /// Spin up `threads` worker threads to render `scene`
+    pub fn new(scene: &Arc<RwLock<impl Scene + 'static>>, threads: usize) -> Renderer {
+        let mut job_tx = Vec::new();
+        let mut workers = Vec::new();
+        for id in 0..threads {
+            let scene = Arc::clone(scene);
+            let (tx, rx) = mpsc::channel();
+            job_tx.push(tx);
+            workers.push(
+                thread::Builder::new()
+                    .name(format!("render-{}", id))
+                    .spawn(move || render_worker(id, scene, rx))
+                    .expect("failed to spawn render thread"),
+            );
+        }
+
+        Renderer {
+            job_tx,
+            workers,
+            current: None,
+        }
+    }
+
+    /// Number of worker threads
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Start rendering a pass over the scene (using this renderer's threads,
+    /// regardless of `opts.threads`), cancelling the previous frame if it's
+    /// still in-flight.
+    ///
+    /// Returns a [RenderProgress] which can be polled for the status of the
+    /// frame, and used to flush progress into `accum`, or an error if any of
+    /// the threads has died.
+    pub fn render(
+        &mut self,
+        opts: RenderOpts,
+        accum: &mut Accumulator,
+    ) -> Result<RenderProgress, RenderError> {
+        if let Some(frame) = self.current.take() {
+            frame.cancelled.store(true, Ordering::Relaxed);
+        }
+
+        let first_sample = accum.start_pass(opts.samples);
+
+        let tiles = tiles(
+            opts.width,
+            opts.height,
+            opts.tile_size,
+            opts.tile_order,
+            opts.seed,
+        );
+
+        let prior = match opts.noise_threshold {
+            Some(_) => accum.stats().to_vec(),
+            None => Vec::new(),
+        };
+
+        let tiles_total = tiles.len();
+        let frame = Arc::new(Frame {
+            tiles,
+            next_tile: AtomicUsize::new(0),
+            cancelled: AtomicBool::new(false),
+            first_sample,
+            prior,
+            opts,
         });
+
+        // create the progress channel
+        let (progress_tx, progress_rx) = mpsc::channel();
+
+        // hand the frame off to the workers (which will start on it once
+        // they've noticed that their previous frame was cancelled)
+        let mut thread_rx = Vec::new();
+        for (thread, tx) in self.job_tx.iter().enumerate() {
+            let (thread_tx, rx) = mpsc::channel();
+            thread_rx.push(rx);
+            let job = Job {
+                frame: Arc::clone(&frame),
+                progress_tx: progress_tx.clone(),
+                thread_tx,
+            };
+            if tx.send(job).is_err() {
+                frame.cancelled.store(true, Ordering::Relaxed);
+                return Err(RenderError { thread });
+            }
+        }
+
+        self.current = Some(Arc::clone(&frame));
+        Ok(RenderProgress {
+            invalidated: false,
+            tiles_done: 0,
+            tiles_total,
+            start: Some(Instant::now()),
+            end: None,
+            samples_taken: 0,
+            rays: RayCounts::default(),
+            thread_busy: vec![Duration::default(); self.threads()],
+            frame: Some(frame),
+            progress_rx: Some(progress_rx),
+            thread_status: vec![ThreadProgress::Active; self.threads()],
+            thread_rx,
+        })
+    }
+}
+
+impl Drop for Renderer {
+    fn drop(&mut self) {
+        if let Some(frame) = self.current.take() {
+            frame.cancelled.store(true, Ordering::Relaxed);
+        }
+        // closing the job channels lets the workers exit
+        self.job_tx.clear();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::environment::Environment;
+    use crate::render::TileOrder;
+    use crate::sampler::SamplerKind;
+    use crate::scenes::Chapter;
+
+    /// A scene whose sky panics as soon as any ray escapes
+    struct PanickingScene(Chapter);
+
+    struct PanickingSky;
+
+    impl Environment for PanickingSky {
+        fn color(&self, _dir: &Vec3) -> Vec3 {
+            panic!("the sky is falling");
+        }
     }
 
-    RenderProgress::new(
-        num_cpus::get(),
-        remaining,
-        progress_rx,
-        thread_rx,
-        thread_term_tx,
-    )
+    impl Scene for PanickingScene {
+        type World = <Chapter as Scene>::World;
+        type Environment = PanickingSky;
+
+        fn get_camera(&self) -> &Camera {
+            self.0.get_camera()
+        }
+        fn get_world(&self) -> &Self::World {
+            self.0.get_world()
+        }
+        fn get_environment(&self) -> &PanickingSky {
+            &PanickingSky
+        }
+        fn enable_freecam(&mut self, cam: Camera) {
+            self.0.enable_freecam(cam)
+        }
+        fn disable_freecam(&mut self) {
+            self.0.disable_freecam()
+        }
+    }
+
+    #[test]
+    fn dead_threads_are_reported() {
+        let opts = RenderOpts {
+            width: 8,
+            height: 8,
+            samples: 1,
+            threads: 2,
+            max_depth: 50,
+            seed: 0,
+            rr_depth: 5,
+            tile_size: 4,
+            tile_order: TileOrder::Scanline,
+            noise_threshold: None,
+            min_samples: 1,
+            max_samples: 1,
+            sampler: SamplerKind::Independent,
+        };
+        let scene = Arc::new(RwLock::new(PanickingScene(Chapter::new())));
+        let mut renderer = Renderer::new(&scene, opts.threads);
+        let mut accum = Accumulator::new(opts.width, opts.height);
+
+        let mut frame = renderer.render(opts, &mut accum).unwrap();
+        assert!(frame.wait_to_buffer(&mut accum).is_err());
+
+        // the dead threads can't take on any more work
+        while renderer.workers.iter().any(|w| !w.is_finished()) {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(renderer.render(opts, &mut accum).is_err());
+    }
 }
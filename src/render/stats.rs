@@ -0,0 +1,101 @@
+//! Render progress and statistics.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::counters::RayCounts;
+
+/// A snapshot of a frame's progress
+#[derive(Debug, Clone)]
+pub struct RenderStats {
+    /// number of tiles rendered so far
+    pub tiles_done: usize,
+    /// number of tiles in the frame
+    pub tiles_total: usize,
+    /// time since the frame started (or how long it took, once it's done)
+    pub elapsed: Duration,
+    /// number of (pixel) samples taken
+    pub samples: u64,
+    pub rays: RayCounts,
+    /// time each render thread has spent rendering this frame
+    pub thread_busy: Vec<Duration>,
+}
+
+impl RenderStats {
+    /// Fraction of the frame which has been rendered, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        if self.tiles_total == 0 {
+            return 1.;
+        }
+        self.tiles_done as f32 / self.tiles_total as f32
+    }
+
+    /// Estimated time until the frame is done (assuming the remaining tiles
+    /// take as long as the ones so far)
+    pub fn eta(&self) -> Option<Duration> {
+        match self.progress() {
+            p if p <= 0. => None,
+            p => Some(self.elapsed.mul_f32((1. - p) / p)),
+        }
+    }
+
+    /// Total number of rays cast
+    pub fn total_rays(&self) -> u64 {
+        self.rays.primary + self.rays.secondary
+    }
+
+    /// Samples taken per second
+    pub fn samples_per_sec(&self) -> f32 {
+        per_sec(self.samples, self.elapsed)
+    }
+
+    /// Rays cast per second
+    pub fn rays_per_sec(&self) -> f32 {
+        per_sec(self.total_rays(), self.elapsed)
+    }
+}
+
+fn per_sec(n: u64, elapsed: Duration) -> f32 {
+    match elapsed.as_secs_f32() {
+        s if s > 0. => n as f32 / s,
+        _ => 0.,
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "progress: {:.1}% ({}/{} tiles) in {:.2?}",
+            self.progress() * 100.,
+            self.tiles_done,
+            self.tiles_total,
+            self.elapsed
+        )?;
+        writeln!(
+            f,
+            "rays: {} ({} primary, {} secondary), {:.2} Mrays/s",
+            self.total_rays(),
+            self.rays.primary,
+            self.rays.secondary,
+            self.rays_per_sec() / 1e6
+        )?;
+        writeln!(
+            f,
+            "intersection tests: {} ({:.1} per ray)",
+            self.rays.intersection_tests,
+            self.rays.intersection_tests as f32 / self.total_rays().max(1) as f32
+        )?;
+        writeln!(
+            f,
+            "samples: {}, {:.2} Msamples/s",
+            self.samples,
+            self.samples_per_sec() / 1e6
+        )?;
+        write!(f, "thread busy time:")?;
+        for busy in &self.thread_busy {
+            write!(f, " {:.2?}", busy)?;
+        }
+        Ok(())
+    }
+}
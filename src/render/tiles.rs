@@ -0,0 +1,249 @@
+//! Splitting the image into tiles, and picking the order they're rendered in.
+
+use std::fmt;
+use std::str::FromStr;
+
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::util::RenderRng;
+
+/// The order in which tiles are handed out to the render threads
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TileOrder {
+    /// Left to right, top to bottom
+    Scanline,
+    /// Shuffled (using the render seed)
+    Random,
+    /// Spiralling outwards from the center of the image
+    Spiral,
+    /// Along a Hilbert curve, which keeps consecutive tiles close together
+    Hilbert,
+}
+
+impl TileOrder {
+    const ALL: [TileOrder; 4] = [
+        TileOrder::Scanline,
+        TileOrder::Random,
+        TileOrder::Spiral,
+        TileOrder::Hilbert,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            TileOrder::Scanline => "scanline",
+            TileOrder::Random => "random",
+            TileOrder::Spiral => "spiral",
+            TileOrder::Hilbert => "hilbert",
+        }
+    }
+}
+
+impl fmt::Display for TileOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for TileOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<TileOrder, String> {
+        TileOrder::ALL
+            .iter()
+            .copied()
+            .find(|o| o.name() == s)
+            .ok_or_else(|| {
+                let names = TileOrder::ALL.iter().map(|o| o.name()).collect::<Vec<_>>();
+                format!(
+                    "unknown tile order '{}' (expected one of: {})",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// A rectangular region of the image
+#[derive(Debug, Copy, Clone)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    /// Iterate over the (x, y) coordinates of the tile's pixels, row by row
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize)> {
+        let Tile {
+            x,
+            y,
+            width,
+            height,
+        } = *self;
+        (y..y + height).flat_map(move |y| (x..x + width).map(move |x| (x, y)))
+    }
+}
+
+/// Split a `width` x `height` image into tiles of (at most) `size` x `size`
+/// pixels, sorted in the given `order`.
+pub fn tiles(width: usize, height: usize, size: usize, order: TileOrder, seed: u64) -> Vec<Tile> {
+    let (nx, ny) = (width.div_ceil(size), height.div_ceil(size));
+    let tile = |(tx, ty): (usize, usize)| Tile {
+        x: tx * size,
+        y: ty * size,
+        width: size.min(width - tx * size),
+        height: size.min(height - ty * size),
+    };
+
+    let coords = match order {
+        TileOrder::Scanline | TileOrder::Random => {
+            let mut coords = (0..ny)
+                .flat_map(|ty| (0..nx).map(move |tx| (tx, ty)))
+                .collect::<Vec<_>>();
+            if order == TileOrder::Random {
+                coords.shuffle(&mut RenderRng::seed_from_u64(seed));
+            }
+            coords
+        }
+        TileOrder::Spiral => spiral(nx, ny),
+        TileOrder::Hilbert => hilbert(nx, ny),
+    };
+
+    coords.into_iter().map(tile).collect()
+}
+
+/// Walk a square spiral outwards from the center of an `nx` x `ny` grid,
+/// skipping cells which fall outside of it.
+fn spiral(nx: usize, ny: usize) -> Vec<(usize, usize)> {
+    const DIRS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+    if nx == 0 || ny == 0 {
+        return Vec::new();
+    }
+
+    let mut coords = Vec::with_capacity(nx * ny);
+    let (mut x, mut y) = (((nx - 1) / 2) as isize, ((ny - 1) / 2) as isize);
+    let visit = |x: isize, y: isize, coords: &mut Vec<_>| {
+        if x >= 0 && y >= 0 && (x as usize) < nx && (y as usize) < ny {
+            coords.push((x as usize, y as usize));
+        }
+    };
+    visit(x, y, &mut coords);
+
+    // legs of the spiral have lengths 1, 1, 2, 2, 3, 3, ...
+    let mut leg = 0;
+    while coords.len() < nx * ny {
+        let (dx, dy) = DIRS[leg % 4];
+        for _ in 0..leg / 2 + 1 {
+            x += dx;
+            y += dy;
+            visit(x, y, &mut coords);
+        }
+        leg += 1;
+    }
+
+    coords
+}
+
+/// Walk a Hilbert curve over the smallest power-of-two square containing an
+/// `nx` x `ny` grid, skipping cells which fall outside of it.
+fn hilbert(nx: usize, ny: usize) -> Vec<(usize, usize)> {
+    let n = nx.max(ny).next_power_of_two();
+    (0..n * n)
+        .map(|d| hilbert_d2xy(n, d))
+        .filter(|&(x, y)| x < nx && y < ny)
+        .collect()
+}
+
+/// Convert a distance `d` along the Hilbert curve filling an `n` x `n` square
+/// (where `n` is a power of two) into (x, y) coordinates.
+fn hilbert_d2xy(n: usize, d: usize) -> (usize, usize) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+    while s < n {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        // rotate the quadrant
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that `coords` visits every cell of an `nx` x `ny` grid exactly
+    /// once
+    fn assert_covers(coords: &[(usize, usize)], nx: usize, ny: usize) {
+        let mut seen = vec![false; nx * ny];
+        for &(x, y) in coords {
+            assert!(x < nx && y < ny, "({}, {}) is outside {}x{}", x, y, nx, ny);
+            assert!(!seen[y * nx + x], "({}, {}) is visited twice", x, y);
+            seen[y * nx + x] = true;
+        }
+        assert_eq!(coords.len(), nx * ny);
+    }
+
+    const GRIDS: [(usize, usize); 9] = [
+        (0, 0),
+        (0, 3),
+        (3, 0),
+        (1, 1),
+        (1, 7),
+        (7, 1),
+        (4, 4),
+        (5, 3),
+        (2, 9),
+    ];
+
+    #[test]
+    fn spiral_visits_every_tile_once() {
+        for &(nx, ny) in &GRIDS {
+            assert_covers(&spiral(nx, ny), nx, ny);
+        }
+    }
+
+    #[test]
+    fn hilbert_visits_every_tile_once() {
+        for &(nx, ny) in &GRIDS {
+            assert_covers(&hilbert(nx, ny), nx, ny);
+        }
+    }
+
+    #[test]
+    fn tiles_cover_the_image() {
+        for &order in &TileOrder::ALL {
+            for &(width, height) in &[(0, 0), (0, 10), (10, 0), (1, 1), (33, 17)] {
+                let tiles = tiles(width, height, 8, order, 0);
+                let mut seen = vec![false; width * height];
+                for (x, y) in tiles.iter().flat_map(Tile::pixels) {
+                    assert!(!seen[y * width + x]);
+                    seen[y * width + x] = true;
+                }
+                assert!(seen.iter().all(|&s| s), "{} missed pixels", order);
+            }
+        }
+    }
+
+    #[test]
+    fn tile_order_from_str() {
+        for &order in &TileOrder::ALL {
+            assert_eq!(order.to_string().parse::<TileOrder>(), Ok(order));
+        }
+        assert!("diagonal".parse::<TileOrder>().is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::util::luminance;
+use crate::vec3::Vec3;
+
+use super::AsColorExt;
+
+/// Operators for compressing high-dynamic-range radiance into the displayable
+/// [0, 1] range.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Tonemap {
+    /// No compression, values outside [0, 1] are simply clamped
+    Linear,
+    /// Reinhard (applied to luminance)
+    Reinhard,
+    /// Reinhard, with luminance at or above the white point mapping to white
+    ReinhardExtended,
+    /// John Hable's filmic curve, from Uncharted 2
+    Hable,
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve
+    Aces,
+}
+
+impl Tonemap {
+    /// All the operators, in the order the preview cycles through them
+    const ALL: [Tonemap; 5] = [
+        Tonemap::Linear,
+        Tonemap::Reinhard,
+        Tonemap::ReinhardExtended,
+        Tonemap::Hable,
+        Tonemap::Aces,
+    ];
+
+    /// Returns the operator after this one (wrapping around)
+    pub fn next(self) -> Tonemap {
+        let i = Tonemap::ALL.iter().position(|&t| t == self).unwrap();
+        Tonemap::ALL[(i + 1) % Tonemap::ALL.len()]
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Tonemap::Linear => "linear",
+            Tonemap::Reinhard => "reinhard",
+            Tonemap::ReinhardExtended => "reinhard-extended",
+            Tonemap::Hable => "hable",
+            Tonemap::Aces => "aces",
+        }
+    }
+
+    /// Map linear radiance `c` into (roughly) [0, 1]
+    fn apply(self, c: Vec3, white_point: f32) -> Vec3 {
+        match self {
+            Tonemap::Linear => c,
+            Tonemap::Reinhard => scale_luminance(c, |l| l / (1. + l)),
+            Tonemap::ReinhardExtended => {
+                let w2 = white_point * white_point;
+                scale_luminance(c, |l| l * (1. + l / w2) / (1. + l))
+            }
+            Tonemap::Hable => {
+                const EXPOSURE_BIAS: f32 = 2.;
+                const WHITE: f32 = 11.2;
+                let curve = |x: f32| {
+                    const A: f32 = 0.15;
+                    const B: f32 = 0.50;
+                    const C: f32 = 0.10;
+                    const D: f32 = 0.20;
+                    const E: f32 = 0.02;
+                    const F: f32 = 0.30;
+                    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+                };
+                let scale = 1. / curve(WHITE);
+                map_channels(c, |x| curve(EXPOSURE_BIAS * x) * scale)
+            }
+            Tonemap::Aces => map_channels(c, |x| {
+                let x = 0.6 * x;
+                (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Tonemap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Tonemap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Tonemap, String> {
+        Tonemap::ALL
+            .iter()
+            .copied()
+            .find(|t| t.name() == s)
+            .ok_or_else(|| {
+                let names = Tonemap::ALL.iter().map(|t| t.name()).collect::<Vec<_>>();
+                format!(
+                    "unknown tone mapping operator '{}' (expected one of: {})",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[inline]
+fn map_channels(c: Vec3, f: impl Fn(f32) -> f32) -> Vec3 {
+    Vec3::new(f(c.x), f(c.y), f(c.z))
+}
+
+/// Apply `f` to the luminance of `c`, preserving its hue
+#[inline]
+fn scale_luminance(c: Vec3, f: impl Fn(f32) -> f32) -> Vec3 {
+    let l = luminance(c);
+    if l <= 0. {
+        return c;
+    }
+    c * (f(l) / l)
+}
+
+/// The sRGB transfer function (i.e: "gamma correction")
+#[inline]
+pub(super) fn srgb_encode(x: f32) -> f32 {
+    if x <= 0.003_130_8 {
+        12.92 * x
+    } else {
+        1.055 * x.powf(1. / 2.4) - 0.055
+    }
+}
+
+/// Options controlling how linear radiance is turned into displayable colors
+#[derive(Debug, Copy, Clone)]
+pub struct DisplayOpts {
+    /// tone mapping operator
+    pub tonemap: Tonemap,
+    /// exposure adjustment (in stops) applied before tone mapping
+    pub exposure: f32,
+    /// smallest luminance mapped to white by [Tonemap::ReinhardExtended]
+    pub white_point: f32,
+}
+
+impl DisplayOpts {
+    /// Expose, tone map, and sRGB-encode linear radiance `c`, returning a
+    /// color with components in [0, 1].
+    #[inline]
+    pub fn apply(&self, c: Vec3) -> Vec3 {
+        let c = c * 2f32.powf(self.exposure);
+        let c = self.tonemap.apply(c, self.white_point);
+        map_channels(c, |x| srgb_encode(x.clamp(0., 1.)))
+    }
+
+    /// Tone map and quantize linear `pixels` into a 0RGB buffer
+    pub fn resolve_to_buffer(&self, pixels: impl IntoIterator<Item = Vec3>, buffer: &mut [u32]) {
+        for (px, c) in buffer.iter_mut().zip(pixels) {
+            *px = self.apply(c).as_color();
+        }
+    }
+}
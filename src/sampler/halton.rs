@@ -0,0 +1,78 @@
+use crate::util::hash;
+
+use super::{to_unit_float, Sampler};
+
+/// Bases of the Halton sequence's dimensions. Any further dimensions fall
+/// back to independent random numbers.
+const PRIMES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// The Halton sequence, randomized with a different (Cranley-Patterson)
+/// rotation for each pixel and dimension.
+#[derive(Debug)]
+pub struct Halton {
+    seed: u64,
+    x: usize,
+    y: usize,
+    index: usize,
+    dim: usize,
+}
+
+impl Halton {
+    pub fn new(seed: u64) -> Halton {
+        Halton {
+            seed,
+            x: 0,
+            y: 0,
+            index: 0,
+            dim: 0,
+        }
+    }
+}
+
+impl Sampler for Halton {
+    fn start_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.x = x;
+        self.y = y;
+        self.index = index;
+        self.dim = 0;
+    }
+
+    fn set_dimension(&mut self, dim: usize) {
+        self.dim = dim;
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        let (x, y, dim) = (self.x as u64, self.y as u64, self.dim as u64);
+        self.dim += 1;
+
+        let base = match PRIMES.get(dim as usize) {
+            Some(&base) => base,
+            None => {
+                let bits = hash(&[self.seed, x, y, self.index as u64, dim]);
+                return to_unit_float(bits as u32);
+            }
+        };
+
+        let rotation = to_unit_float(hash(&[self.seed, x, y, dim]) as u32);
+        let u = radical_inverse(base, self.index as u64) + rotation as f64;
+        // (rounding to f32 may land exactly on 1.0)
+        (u.fract() as f32).min(1. - f32::EPSILON / 2.)
+    }
+}
+
+/// Reflect the digits of `i` (in base `base`) about the decimal point
+fn radical_inverse(base: u32, mut i: u64) -> f64 {
+    let base = base as u64;
+    let inv_base = 1. / base as f64;
+    let mut scale = inv_base;
+    let mut u = 0.;
+    while i > 0 {
+        u += (i % base) as f64 * scale;
+        i /= base;
+        scale *= inv_base;
+    }
+    u
+}
@@ -0,0 +1,35 @@
+use rand::Rng;
+
+use crate::util::{sample_rng, RenderRng};
+
+use super::Sampler;
+
+/// Independent (pseudo-)random numbers for every dimension
+#[derive(Debug)]
+pub struct Independent {
+    seed: u64,
+    rng: RenderRng,
+}
+
+impl Independent {
+    pub fn new(seed: u64) -> Independent {
+        Independent {
+            seed,
+            rng: sample_rng(seed, 0, 0, 0),
+        }
+    }
+}
+
+impl Sampler for Independent {
+    fn start_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.rng = sample_rng(self.seed, x, y, index);
+    }
+
+    fn set_dimension(&mut self, _dim: usize) {
+        // every dimension is independent anyways
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        self.rng.gen()
+    }
+}
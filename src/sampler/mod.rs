@@ -0,0 +1,130 @@
+//! Sample generators, which supply the numbers used to make each of a pixel's
+//! samples (its position within the pixel, where it passes through the lens,
+//! and the decisions made each time its path bounces).
+//!
+//! Better-distributed numbers cover these dimensions more evenly than
+//! independent random numbers do, so images converge faster.
+
+use std::fmt;
+use std::str::FromStr;
+
+mod halton;
+mod independent;
+mod sobol;
+mod stratified;
+
+pub use halton::Halton;
+pub use independent::Independent;
+pub use sobol::Sobol;
+pub use stratified::Stratified;
+
+/// Which dimensions of a sample are used for what
+pub mod dim {
+    /// (2D) position within the pixel
+    pub const PIXEL: usize = 0;
+    /// (2D) position on the lens
+    pub const LENS: usize = 2;
+
+    /// Dimensions reserved for each bounce
+    const PER_BOUNCE: usize = 8;
+
+    /// (up to 3D) scattering off of the surface, relative to [bounce]
+    pub const SCATTER: usize = 0;
+    /// (1D) russian roulette, relative to [bounce]
+    pub const RUSSIAN_ROULETTE: usize = 3;
+    /// (1D) which light to sample directly, followed by (2D) the point
+    /// sampled on it, relative to [bounce]
+    pub const LIGHT: usize = 4;
+
+    /// First dimension used by bounce number `depth`
+    pub fn bounce(depth: usize) -> usize {
+        LENS + 2 + depth * PER_BOUNCE
+    }
+}
+
+/// A generator of sample points in the unit hypercube. Each of a pixel's
+/// samples is a point, whose coordinates (i.e: "dimensions") are consumed in
+/// order, with values in [0, 1).
+///
+/// Samples are a deterministic function of the pixel, sample index, and seed,
+/// so renders don't depend on which thread renders which pixel.
+pub trait Sampler {
+    /// Start generating sample number `index` of pixel (`x`, `y`), from
+    /// dimension 0
+    fn start_sample(&mut self, x: usize, y: usize, index: usize);
+
+    /// Skip ahead (or back) to dimension `dim` of the current sample
+    fn set_dimension(&mut self, dim: usize);
+
+    /// Returns the next dimension of the current sample
+    fn next_1d(&mut self) -> f32;
+
+    /// Returns the next two dimensions of the current sample
+    fn next_2d(&mut self) -> (f32, f32) {
+        let u = self.next_1d();
+        (u, self.next_1d())
+    }
+}
+
+/// The available samplers
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SamplerKind {
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+impl SamplerKind {
+    const ALL: [SamplerKind; 4] = [
+        SamplerKind::Independent,
+        SamplerKind::Stratified,
+        SamplerKind::Halton,
+        SamplerKind::Sobol,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            SamplerKind::Independent => "independent",
+            SamplerKind::Stratified => "stratified",
+            SamplerKind::Halton => "halton",
+            SamplerKind::Sobol => "sobol",
+        }
+    }
+}
+
+impl fmt::Display for SamplerKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for SamplerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<SamplerKind, String> {
+        SamplerKind::ALL
+            .iter()
+            .copied()
+            .find(|k| k.name() == s)
+            .ok_or_else(|| {
+                let names = SamplerKind::ALL
+                    .iter()
+                    .map(|k| k.name())
+                    .collect::<Vec<_>>();
+                format!(
+                    "unknown sampler '{}' (expected one of: {})",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Convert 32 random bits into a float in [0, 1)
+#[inline]
+fn to_unit_float(bits: u32) -> f32 {
+    // only 24 bits fit in an f32's mantissa, and rounding the rest could
+    // produce 1.0
+    (bits >> 8) as f32 / (1 << 24) as f32
+}
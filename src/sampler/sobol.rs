@@ -0,0 +1,127 @@
+use crate::util::hash;
+
+use super::{to_unit_float, Sampler};
+
+/// Sobol direction numbers for the first four dimensions (generated from Joe
+/// and Kuo's primitive polynomials and initial values)
+const DIRECTIONS: [[u32; 32]; 4] = [
+    directions(0, 0, [1, 0, 0]),
+    directions(1, 0, [1, 0, 0]),
+    directions(2, 1, [1, 3, 0]),
+    directions(3, 1, [1, 3, 1]),
+];
+
+/// Generate the direction numbers for a dimension whose primitive polynomial
+/// has degree `s` and (inner) coefficients `a`, with initial values `m`.
+/// Degree 0 is used for the first dimension (the van der Corput sequence).
+const fn directions(s: usize, a: u32, m_init: [u32; 3]) -> [u32; 32] {
+    let mut m = [0u32; 32];
+    let mut i = 0;
+    while i < 32 {
+        if s == 0 {
+            m[i] = 1;
+        } else if i < s {
+            m[i] = m_init[i];
+        } else {
+            let mut v = m[i - s] ^ (m[i - s] << s);
+            let mut k = 1;
+            while k < s {
+                if (a >> (s - 1 - k)) & 1 == 1 {
+                    v ^= m[i - k] << k;
+                }
+                k += 1;
+            }
+            m[i] = v;
+        }
+        i += 1;
+    }
+
+    let mut v = [0u32; 32];
+    let mut i = 0;
+    while i < 32 {
+        v[i] = m[i] << (31 - i);
+        i += 1;
+    }
+    v
+}
+
+/// The Sobol sequence, randomized using hash-based Owen scrambling.
+///
+/// Dimensions are handled in groups of four, with each group using its own
+/// (scrambled) ordering of the sequence, so that higher dimensions are
+/// decorrelated from lower ones.
+///
+/// See "Practical Hash-based Owen Scrambling" (Burley 2020)
+#[derive(Debug)]
+pub struct Sobol {
+    seed: u64,
+    x: usize,
+    y: usize,
+    index: usize,
+    dim: usize,
+}
+
+impl Sobol {
+    pub fn new(seed: u64) -> Sobol {
+        Sobol {
+            seed,
+            x: 0,
+            y: 0,
+            index: 0,
+            dim: 0,
+        }
+    }
+}
+
+impl Sampler for Sobol {
+    fn start_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.x = x;
+        self.y = y;
+        self.index = index;
+        self.dim = 0;
+    }
+
+    fn set_dimension(&mut self, dim: usize) {
+        self.dim = dim;
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        let (group, d) = (self.dim / 4, self.dim % 4);
+        self.dim += 1;
+
+        let seed = hash(&[self.seed, self.x as u64, self.y as u64, group as u64]);
+        let index = nested_uniform_scramble(self.index as u32, seed as u32);
+        let u = sobol(index, d);
+        to_unit_float(nested_uniform_scramble(u, hash(&[seed, d as u64]) as u32))
+    }
+}
+
+/// Returns (the bits of) dimension `dim` of the `index`th Sobol point
+fn sobol(mut index: u32, dim: usize) -> u32 {
+    let mut u = 0;
+    let mut bit = 0;
+    while index != 0 {
+        if index & 1 == 1 {
+            u ^= DIRECTIONS[dim][bit];
+        }
+        index >>= 1;
+        bit += 1;
+    }
+    u
+}
+
+/// Owen-scramble the bits of `x` (i.e: randomly flip each bit, based on all
+/// the more significant bits)
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+/// A hash in which each bit only affects more significant bits
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x
+}
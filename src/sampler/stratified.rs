@@ -0,0 +1,143 @@
+use crate::util::hash;
+
+use super::{to_unit_float, Sampler};
+
+/// Jittered stratified samples: the samples taken in each pass are spread
+/// across equally sized strata (a grid of cells, for pairs of dimensions),
+/// with a random point picked within each stratum.
+///
+/// Strata are assigned to samples in a different (random) order for each
+/// pixel and dimension, to avoid correlations between dimensions.
+#[derive(Debug)]
+pub struct Stratified {
+    seed: u64,
+    /// number of samples per pass (i.e: the number of strata)
+    samples: usize,
+    x: usize,
+    y: usize,
+    index: usize,
+    dim: usize,
+}
+
+impl Stratified {
+    /// Create a sampler for passes of `samples` samples per-pixel
+    pub fn new(seed: u64, samples: usize) -> Stratified {
+        Stratified {
+            seed,
+            samples: samples.max(1),
+            x: 0,
+            y: 0,
+            index: 0,
+            dim: 0,
+        }
+    }
+
+    /// Returns the stratum (out of `strata`) assigned to the current sample,
+    /// along with some random bits used to jitter within it.
+    fn stratum(&self, strata: usize) -> (usize, u64) {
+        let pass = self.index / self.samples;
+        let i = self.index % self.samples;
+        let (x, y, dim) = (self.x as u64, self.y as u64, self.dim as u64);
+        let order = hash(&[self.seed, x, y, pass as u64, dim]);
+        let jitter = hash(&[self.seed, x, y, self.index as u64, dim, 1]);
+        (
+            permute(i as u32, strata as u32, order as u32) as usize,
+            jitter,
+        )
+    }
+}
+
+impl Sampler for Stratified {
+    fn start_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.x = x;
+        self.y = y;
+        self.index = index;
+        self.dim = 0;
+    }
+
+    fn set_dimension(&mut self, dim: usize) {
+        self.dim = dim;
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        let (stratum, jitter) = self.stratum(self.samples);
+        self.dim += 1;
+        (stratum as f32 + to_unit_float(jitter as u32)) / self.samples as f32
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        // the smallest (roughly square) grid with at least one cell per sample
+        let nx = (self.samples as f32).sqrt().ceil() as usize;
+        let ny = self.samples.div_ceil(nx);
+
+        let (cell, jitter) = self.stratum(nx * ny);
+        self.dim += 2;
+        (
+            ((cell % nx) as f32 + to_unit_float(jitter as u32)) / nx as f32,
+            ((cell / nx) as f32 + to_unit_float((jitter >> 32) as u32)) / ny as f32,
+        )
+    }
+}
+
+/// Returns the `i`th element of a random permutation of `0..len` (picked by
+/// `seed`), without having to store the permutation.
+///
+/// See "Correlated Multi-Jittered Sampling" (Kensler 2013)
+fn permute(mut i: u32, len: u32, seed: u32) -> u32 {
+    let mut w = len - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    // permute within the next power of two, until the result lands in range
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < len {
+            break;
+        }
+    }
+    ((i as u64 + seed as u64) % len as u64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permute_is_a_permutation() {
+        for &len in &[1, 2, 3, 7, 16, 100, 257] {
+            for &seed in &[0, 1, 0xdead_beef, u32::MAX - 3, u32::MAX] {
+                let mut seen = vec![false; len as usize];
+                for i in 0..len {
+                    let j = permute(i, len, seed);
+                    assert!(j < len, "permute({}, {}, {}) = {}", i, len, seed, j);
+                    assert!(
+                        !seen[j as usize],
+                        "permute(_, {}, {}) repeats {}",
+                        len, seed, j
+                    );
+                    seen[j as usize] = true;
+                }
+            }
+        }
+    }
+}
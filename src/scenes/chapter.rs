@@ -1,4 +1,5 @@
 use crate::camera::{Camera, CameraOpts};
+use crate::environment::Gradient;
 use crate::hittable::{HittableT, Sphere};
 use crate::material;
 use crate::vec3::Vec3;
@@ -9,15 +10,21 @@ use super::Scene;
 pub struct Chapter {
     camera: Camera,
     spheres: Vec<HittableT>,
+    sky: Gradient,
 }
 
 impl Scene for Chapter {
     type World = Vec<HittableT>;
+    type Environment = Gradient;
 
     fn get_world(&self) -> &Vec<HittableT> {
         &self.spheres
     }
 
+    fn get_environment(&self) -> &Gradient {
+        &self.sky
+    }
+
     fn get_camera(&self) -> &Camera {
         &self.camera
     }
@@ -99,6 +106,7 @@ impl Chapter {
                 focus_dist: (look_from - look_at).length(),
             }),
             spheres,
+            sky: Gradient::default(),
         }
     }
 }
@@ -0,0 +1,775 @@
+//! Scenes loaded from a TOML scene description file.
+//!
+//! A scene file consists of a `[camera]` table, and a list of `[[objects]]`,
+//! each of which specifies its `type` and `material`.
+//!
+//! An optional `[environment]` table sets what rays see when they escape the
+//! scene (defaulting to the usual blue-white sky gradient), and an optional
+//! list of `[[lights]]` adds punctual lights (`point`, `spot`, and
+//! `directional`) to the scene.
+//!
+//! The `albedo` of `lambertian` and `metal` materials may either be a plain
+//! color, or a texture (`solid`, `checker`, `image`, or `noise`).
+//!
+//! For example:
+//!
+//! ```toml
+//! [camera]
+//! look_from = [3.0, 3.0, 2.0]
+//! look_at = [0.0, 0.0, -1.0]
+//! hfov = 45.0
+//! aperture = 0.1
+//!
+//! [environment]
+//! type = "hdr"
+//! path = "sky.hdr"
+//!
+//! [[objects]]
+//! type = "sphere"
+//! center = [0.0, 0.0, -1.0]
+//! radius = 0.5
+//! material = { type = "lambertian", albedo = [0.1, 0.2, 0.5] }
+//!
+//! [[objects]]
+//! type = "inf_plane"
+//! center = [0.0, -0.5, 0.0]
+//! normal = [0.0, 1.0, 0.0]
+//! material.type = "lambertian"
+//! material.albedo = { type = "checker", even = [0.9, 0.9, 0.9], odd = [0.2, 0.3, 0.1], size = 0.5 }
+//!
+//! [[lights]]
+//! type = "directional"
+//! direction = [1.0, 2.0, 0.5]
+//! irradiance = [3.0, 2.9, 2.7]
+//! angular_diameter = 0.53
+//! ```
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+
+use crate::camera::{Camera, CameraOpts};
+use crate::environment::{EnvironmentMap, EnvironmentT, Gradient, SolidColor};
+use crate::hittable::{
+    Aabb, Bvh, Emitter, Hittable, HittableT, InfPlane, Quad, Sphere, Triangle, TriangleMesh,
+};
+use crate::light::{
+    DirectionalLight, LightSet, LightT, PointLight, QuadLight, SphereLight, SpotLight,
+    TriangleLight,
+};
+use crate::material::{self, MaterialT};
+use crate::obj::{self, ObjError};
+use crate::texture::{Checker, ImageTexture, Noise, NoiseKind, Solid, TextureT, WrapMode};
+use crate::vec3::Vec3;
+
+use super::Scene;
+
+/// Error returned when a scene file fails to load
+#[derive(Debug)]
+pub enum LoadError {
+    /// Could not read the scene file
+    Io(PathBuf, io::Error),
+    /// Scene file is malformed (error includes line and column information)
+    Parse(PathBuf, toml::de::Error),
+    /// Could not import a mesh referenced by the scene file
+    Obj(ObjError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(path, e) => write!(f, "could not read {}: {}", path.display(), e),
+            LoadError::Parse(path, e) => write!(f, "{}: {}", path.display(), e),
+            LoadError::Obj(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<ObjError> for LoadError {
+    fn from(e: ObjError) -> LoadError {
+        LoadError::Obj(e)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDesc {
+    camera: CameraDesc,
+    #[serde(default)]
+    environment: EnvironmentDesc,
+    #[serde(default)]
+    objects: Vec<HittableDesc>,
+    #[serde(default)]
+    lights: Vec<LightDesc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    look_from: Vec3,
+    look_at: Vec3,
+    #[serde(default = "default_vup")]
+    vup: Vec3,
+    /// in degrees
+    hfov: f32,
+    #[serde(default)]
+    aperture: f32,
+    /// defaults to the distance between `look_from` and `look_at`
+    focus_dist: Option<f32>,
+}
+
+fn default_vup() -> Vec3 {
+    Vec3::new(0., 1., 0.)
+}
+
+impl From<CameraDesc> for CameraOpts {
+    fn from(desc: CameraDesc) -> CameraOpts {
+        CameraOpts {
+            origin: desc.look_from,
+            direction: (desc.look_from - desc.look_at).normalize(),
+            vup: desc.vup,
+            hfov: desc.hfov,
+            aspect: 9999., // dummy value, should depend on output medium
+            aperture: desc.aperture,
+            focus_dist: desc
+                .focus_dist
+                .unwrap_or_else(|| (desc.look_from - desc.look_at).length()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum EnvironmentDesc {
+    Solid {
+        color: Vec3,
+    },
+    Gradient {
+        bottom: Vec3,
+        top: Vec3,
+    },
+    /// Equirectangular Radiance `.hdr` map (path is relative to the scene
+    /// file)
+    Hdr {
+        path: PathBuf,
+        /// rotation about the y axis, in degrees
+        #[serde(default)]
+        rotation: f32,
+        #[serde(default = "default_intensity")]
+        intensity: f32,
+    },
+}
+
+fn default_intensity() -> f32 {
+    1.
+}
+
+impl Default for EnvironmentDesc {
+    fn default() -> EnvironmentDesc {
+        let Gradient { bottom, top } = Gradient::default();
+        EnvironmentDesc::Gradient { bottom, top }
+    }
+}
+
+impl EnvironmentDesc {
+    /// Instantiate the described environment, resolving any paths relative to
+    /// `base_dir`.
+    fn into_environment(self, base_dir: &Path) -> Result<EnvironmentT, LoadError> {
+        Ok(match self {
+            EnvironmentDesc::Solid { color } => SolidColor::new_environment(color),
+            EnvironmentDesc::Gradient { bottom, top } => Gradient::new_environment(bottom, top),
+            EnvironmentDesc::Hdr {
+                path,
+                rotation,
+                intensity,
+            } => {
+                let path = base_dir.join(path);
+                EnvironmentMap::new_environment(&path, rotation, intensity)
+                    .map_err(|e| LoadError::Io(path, e))?
+            }
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum LightDesc {
+    Point {
+        position: Vec3,
+        /// radiant intensity
+        intensity: Vec3,
+    },
+    Spot {
+        position: Vec3,
+        /// direction the light is pointing in
+        direction: Vec3,
+        /// radiant intensity
+        intensity: Vec3,
+        /// half-angle (in degrees) within which the light is at full intensity
+        inner_angle: f32,
+        /// half-angle (in degrees) beyond which the light is off
+        outer_angle: f32,
+    },
+    /// An infinitely far away light (e.g: the sun)
+    Directional {
+        /// direction pointing towards the light
+        direction: Vec3,
+        irradiance: Vec3,
+        /// in degrees (0 for perfectly sharp shadows)
+        #[serde(default)]
+        angular_diameter: f32,
+    },
+}
+
+impl From<LightDesc> for LightT {
+    fn from(desc: LightDesc) -> LightT {
+        match desc {
+            LightDesc::Point {
+                position,
+                intensity,
+            } => PointLight::new_light(position, intensity),
+            LightDesc::Spot {
+                position,
+                direction,
+                intensity,
+                inner_angle,
+                outer_angle,
+            } => SpotLight::new_light(position, direction, intensity, inner_angle, outer_angle),
+            LightDesc::Directional {
+                direction,
+                irradiance,
+                angular_diameter,
+            } => DirectionalLight::new_light(direction, irradiance, angular_diameter),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum HittableDesc {
+    Sphere {
+        center: Vec3,
+        radius: f32,
+        material: MaterialDesc,
+    },
+    InfPlane {
+        center: Vec3,
+        normal: Vec3,
+        material: MaterialDesc,
+    },
+    /// Parallelogram spanned by edges `u` and `v` from corner `q`
+    Quad {
+        q: Vec3,
+        u: Vec3,
+        v: Vec3,
+        material: MaterialDesc,
+    },
+    Triangle {
+        vertices: [Vec3; 3],
+        material: MaterialDesc,
+    },
+    Mesh(MeshDesc),
+    /// Meshes imported from a Wavefront OBJ file (path is relative to the
+    /// scene file). Uses the OBJ's MTL materials, unless a material is given.
+    Obj {
+        path: PathBuf,
+        material: Option<MaterialDesc>,
+    },
+}
+
+/// An indexed triangle mesh, validated to have in-bounds indices
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "RawMeshDesc")]
+struct MeshDesc {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    texcoords: Vec<[f32; 2]>,
+    indices: Vec<[usize; 3]>,
+    material: MaterialDesc,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawMeshDesc {
+    positions: Vec<Vec3>,
+    /// per-vertex normals (optional)
+    #[serde(default)]
+    normals: Vec<Vec3>,
+    /// per-vertex texture coordinates (optional)
+    #[serde(default)]
+    texcoords: Vec<[f32; 2]>,
+    indices: Vec<[usize; 3]>,
+    material: MaterialDesc,
+}
+
+impl TryFrom<RawMeshDesc> for MeshDesc {
+    type Error = String;
+
+    fn try_from(raw: RawMeshDesc) -> Result<MeshDesc, String> {
+        if !raw.normals.is_empty() && raw.normals.len() != raw.positions.len() {
+            return Err(format!(
+                "mesh has {} positions, but {} normals",
+                raw.positions.len(),
+                raw.normals.len()
+            ));
+        }
+
+        if !raw.texcoords.is_empty() && raw.texcoords.len() != raw.positions.len() {
+            return Err(format!(
+                "mesh has {} positions, but {} texcoords",
+                raw.positions.len(),
+                raw.texcoords.len()
+            ));
+        }
+
+        if let Some(i) = raw
+            .indices
+            .iter()
+            .flatten()
+            .find(|&&i| i >= raw.positions.len())
+        {
+            return Err(format!(
+                "mesh index {} is out of bounds (mesh has {} positions)",
+                i,
+                raw.positions.len()
+            ));
+        }
+
+        Ok(MeshDesc {
+            positions: raw.positions,
+            normals: raw.normals,
+            texcoords: raw.texcoords,
+            indices: raw.indices,
+            material: raw.material,
+        })
+    }
+}
+
+impl HittableDesc {
+    /// Instantiate the described object(s), resolving any paths relative to
+    /// `base_dir`. Any light-emitting objects are also added to `lights`.
+    fn into_hittables(
+        self,
+        base_dir: &Path,
+        lights: &mut Vec<LightT>,
+    ) -> Result<Vec<HittableT>, LoadError> {
+        let first_light = lights.len();
+        let hittable = match self {
+            HittableDesc::Sphere {
+                center,
+                radius,
+                material,
+            } => {
+                if let Some(emit) = material.emission() {
+                    lights.push(SphereLight::new_light(center, radius, emit));
+                }
+                Sphere::new_hittable(center, radius, material.into_material(base_dir)?)
+            }
+            // infinite planes can't be sampled, so they only light the scene
+            // when paths happen to hit them
+            HittableDesc::InfPlane {
+                center,
+                normal,
+                material,
+            } => InfPlane::new_hittable(
+                center,
+                normal.normalize(),
+                material.into_material(base_dir)?,
+            ),
+            HittableDesc::Quad { q, u, v, material } => {
+                if let Some(emit) = material.emission() {
+                    lights.push(QuadLight::new_light(q, u, v, emit));
+                }
+                Quad::new_hittable(q, u, v, material.into_material(base_dir)?)
+            }
+            HittableDesc::Triangle {
+                vertices: [v0, v1, v2],
+                material,
+            } => {
+                if let Some(emit) = material.emission() {
+                    lights.push(TriangleLight::new_light(v0, v1, v2, emit));
+                }
+                Triangle::new_hittable(v0, v1, v2, material.into_material(base_dir)?)
+            }
+            HittableDesc::Mesh(mesh) => {
+                if let Some(emit) = mesh.material.emission() {
+                    lights.extend(triangle_lights(&mesh.positions, &mesh.indices, emit));
+                }
+                TriangleMesh::new_hittable(
+                    mesh.positions,
+                    mesh.normals,
+                    mesh.texcoords,
+                    mesh.indices,
+                    mesh.material.into_material(base_dir)?,
+                )
+            }
+            HittableDesc::Obj { path, material } => {
+                let meshes = obj::load(base_dir.join(path))?;
+                let mut hittables = Vec::new();
+                for mesh in meshes {
+                    let first_light = lights.len();
+                    let emission = match &material {
+                        Some(material) => material.emission(),
+                        None => Some(mesh.material.ke).filter(|ke| ke.squared_length() > 0.),
+                    };
+                    if let Some(emit) = emission {
+                        lights.extend(triangle_lights(&mesh.positions, &mesh.indices, emit));
+                    }
+
+                    let hittable = match &material {
+                        Some(material) => {
+                            mesh.into_hittable_with(material.clone().into_material(base_dir)?)
+                        }
+                        None => mesh.into_hittable(),
+                    };
+                    hittables.push(tag_lights(hittable, first_light, lights));
+                }
+                return Ok(hittables);
+            }
+        };
+
+        Ok(vec![tag_lights(hittable, first_light, lights)])
+    }
+}
+
+/// Wraps `hittable` in an [Emitter] if any lights were added for it (i.e: if
+/// `lights` grew past `first_light`)
+fn tag_lights(hittable: HittableT, first_light: usize, lights: &[LightT]) -> HittableT {
+    if lights.len() > first_light {
+        Emitter::new_hittable(hittable, first_light)
+    } else {
+        hittable
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDesc {
+    Lambertian { albedo: TextureDesc },
+    Metal { albedo: TextureDesc, fuzz: f32 },
+    Dielectric { ref_idx: f32 },
+    DiffuseLight { emit: Vec3 },
+    Void,
+}
+
+impl MaterialDesc {
+    /// Returns the radiance emitted by the material (if it emits any light)
+    fn emission(&self) -> Option<Vec3> {
+        match *self {
+            MaterialDesc::DiffuseLight { emit } => Some(emit),
+            _ => None,
+        }
+    }
+}
+
+/// Returns a light for each of a mesh's faces
+fn triangle_lights<'a>(
+    positions: &'a [Vec3],
+    indices: &'a [[usize; 3]],
+    emit: Vec3,
+) -> impl Iterator<Item = LightT> + 'a {
+    indices.iter().map(move |&[i0, i1, i2]| {
+        TriangleLight::new_light(positions[i0], positions[i1], positions[i2], emit)
+    })
+}
+
+impl MaterialDesc {
+    /// Instantiate the described material, resolving any paths relative to
+    /// `base_dir`.
+    fn into_material(self, base_dir: &Path) -> Result<MaterialT, LoadError> {
+        Ok(match self {
+            MaterialDesc::Lambertian { albedo } => {
+                material::Lambertian::new_material(albedo.into_texture(base_dir)?)
+            }
+            MaterialDesc::Metal { albedo, fuzz } => {
+                material::Metal::new_material(albedo.into_texture(base_dir)?, fuzz)
+            }
+            MaterialDesc::Dielectric { ref_idx } => material::Dielectric::new_material(ref_idx),
+            MaterialDesc::DiffuseLight { emit } => material::DiffuseLight::new_material(emit),
+            MaterialDesc::Void => material::Void::new_material(),
+        })
+    }
+}
+
+/// Either a plain color, or a texture table
+#[derive(Debug, Clone)]
+enum TextureDesc {
+    Color(Vec3),
+    Texture(TextureKindDesc),
+}
+
+// (instead of `#[serde(untagged)]`, which would bury any error inside a
+// texture table under "data did not match any variant")
+impl<'de> Deserialize<'de> for TextureDesc {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<TextureDesc, D::Error> {
+        struct TextureVisitor;
+
+        impl<'de> Visitor<'de> for TextureVisitor {
+            type Value = TextureDesc;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a color (e.g: `[0.5, 0.5, 0.5]`) or a texture table (with a `type`)")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<TextureDesc, A::Error> {
+                Vec3::deserialize(SeqAccessDeserializer::new(seq)).map(TextureDesc::Color)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<TextureDesc, A::Error> {
+                TextureKindDesc::deserialize(MapAccessDeserializer::new(map))
+                    .map(TextureDesc::Texture)
+            }
+        }
+
+        deserializer.deserialize_any(TextureVisitor)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureKindDesc {
+    Solid {
+        color: Vec3,
+    },
+    /// 3D checkerboard of cubes of `size`
+    Checker {
+        even: Box<TextureDesc>,
+        odd: Box<TextureDesc>,
+        #[serde(default = "default_checker_size")]
+        size: CheckerSize,
+    },
+    /// `.png` or `.ppm` image (path is relative to the scene file)
+    Image {
+        path: PathBuf,
+        #[serde(default = "default_wrap")]
+        wrap: WrapMode,
+    },
+    Noise {
+        kind: NoiseKind,
+        /// frequency of the noise
+        #[serde(default = "default_noise_scale")]
+        scale: f32,
+        #[serde(default = "default_noise_color")]
+        color: Vec3,
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+/// Size of a checkerboard's cubes, validated to be positive (and finite)
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(try_from = "f32")]
+struct CheckerSize(f32);
+
+impl TryFrom<f32> for CheckerSize {
+    type Error = String;
+
+    fn try_from(size: f32) -> Result<CheckerSize, String> {
+        if size.is_finite() && size > 0. {
+            Ok(CheckerSize(size))
+        } else {
+            Err(format!("checker size must be positive, got {}", size))
+        }
+    }
+}
+
+fn default_checker_size() -> CheckerSize {
+    CheckerSize(1.)
+}
+
+fn default_wrap() -> WrapMode {
+    WrapMode::Repeat
+}
+
+fn default_noise_scale() -> f32 {
+    1.
+}
+
+fn default_noise_color() -> Vec3 {
+    Vec3::new(1., 1., 1.)
+}
+
+impl TextureDesc {
+    /// Instantiate the described texture, resolving any paths relative to
+    /// `base_dir`.
+    fn into_texture(self, base_dir: &Path) -> Result<TextureT, LoadError> {
+        let desc = match self {
+            TextureDesc::Color(color) => return Ok(color.into()),
+            TextureDesc::Texture(desc) => desc,
+        };
+        Ok(match desc {
+            TextureKindDesc::Solid { color } => Solid::new_texture(color),
+            TextureKindDesc::Checker { even, odd, size } => Checker::new_texture(
+                even.into_texture(base_dir)?,
+                odd.into_texture(base_dir)?,
+                size.0,
+            ),
+            TextureKindDesc::Image { path, wrap } => {
+                let path = base_dir.join(path);
+                ImageTexture::new_texture(&path, wrap).map_err(|e| LoadError::Io(path, e))?
+            }
+            TextureKindDesc::Noise {
+                kind,
+                scale,
+                color,
+                seed,
+            } => Noise::new_texture(kind, scale, color, seed),
+        })
+    }
+}
+
+/// A Scene loaded from a scene description file.
+pub struct FileScene {
+    camera: Camera,
+    objects: Bvh<HittableT>,
+    environment: EnvironmentT,
+    lights: LightSet,
+}
+
+impl FileScene {
+    /// Load a scene from the TOML scene description file at `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<FileScene, LoadError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| LoadError::Io(path.into(), e))?;
+        let desc: SceneDesc =
+            toml::from_str(&text).map_err(|e| LoadError::Parse(path.into(), e))?;
+
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut objects = Vec::new();
+        let mut lights = Vec::new();
+        for object in desc.objects {
+            objects.extend(object.into_hittables(base_dir, &mut lights)?);
+        }
+        lights.extend(desc.lights.into_iter().map(LightT::from));
+
+        // (unbounded objects are left out, as they'd be lit by far away
+        // lights everywhere anyway)
+        let bounds = objects
+            .iter()
+            .filter_map(|o| o.bounding_box())
+            .fold(Aabb::empty(), |a, b| a.union(&b));
+        let scene_radius = if bounds.min.x <= bounds.max.x {
+            0.5 * (bounds.max - bounds.min).length()
+        } else {
+            1.
+        };
+
+        Ok(FileScene {
+            camera: Camera::new(desc.camera.into()),
+            objects: Bvh::new(objects),
+            environment: desc.environment.into_environment(base_dir)?,
+            lights: LightSet::new(lights, scene_radius),
+        })
+    }
+}
+
+impl Scene for FileScene {
+    type World = Bvh<HittableT>;
+    type Environment = EnvironmentT;
+
+    fn get_camera(&self) -> &Camera {
+        &self.camera
+    }
+    fn enable_freecam(&mut self, camera: Camera) {
+        self.camera = camera;
+    }
+    fn disable_freecam(&mut self) {}
+
+    fn get_world(&self) -> &Bvh<HittableT> {
+        &self.objects
+    }
+
+    fn get_environment(&self) -> &EnvironmentT {
+        &self.environment
+    }
+
+    fn get_lights(&self) -> &LightSet {
+        &self.lights
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_material(s: &str) -> Result<MaterialDesc, String> {
+        toml::from_str(s).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn albedos_are_colors_or_textures() {
+        let material = parse_material("type = 'lambertian'\nalbedo = [0.5, 0.5, 0.5]").unwrap();
+        assert!(matches!(
+            material,
+            MaterialDesc::Lambertian {
+                albedo: TextureDesc::Color(_)
+            }
+        ));
+
+        let material = parse_material(
+            "type = 'metal'\nfuzz = 0.1\n\
+             albedo = { type = 'checker', even = [1.0, 1.0, 1.0], odd = { type = 'noise', kind = 'marble' } }",
+        )
+        .unwrap();
+        assert!(matches!(
+            material,
+            MaterialDesc::Metal {
+                albedo: TextureDesc::Texture(TextureKindDesc::Checker { .. }),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let err =
+            parse_material("type = 'metal'\nalbedo = [1.0, 1.0, 1.0]\nfuz = 0.3").unwrap_err();
+        assert!(err.contains("unknown field `fuz`"), "{}", err);
+
+        let parse_object = |s: &str| toml::from_str::<HittableDesc>(s).map_err(|e| e.to_string());
+        let err = parse_object(
+            "type = 'sphere'\ncenter = [0.0, 0.0, 0.0]\nradious = 2.0\n\
+             material = { type = 'void' }",
+        )
+        .unwrap_err();
+        assert!(err.contains("unknown field `radious`"), "{}", err);
+
+        let mesh =
+            "type = 'mesh'\npositions = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]\n\
+                    indices = [[0, 1, 2]]\nmaterial = { type = 'void' }";
+        assert!(parse_object(mesh).is_ok());
+        let err = parse_object(&format!("{}\nnormal = []", mesh)).unwrap_err();
+        assert!(err.contains("unknown field `normal`"), "{}", err);
+    }
+
+    #[test]
+    fn texture_errors_are_precise() {
+        let err = |albedo: &str| {
+            parse_material(&format!("type = 'lambertian'\nalbedo = {}", albedo)).unwrap_err()
+        };
+        assert!(err("'red'").contains("expected a color"));
+        assert!(err("[1.0, 1.0]").contains("invalid length 2"));
+        assert!(
+            err("{ type = 'solid', colour = [1.0, 1.0, 1.0] }").contains("unknown field `colour`")
+        );
+        assert!(err("{ type = 'noise', kind = 'swirl' }").contains("unknown variant `swirl`"));
+        for size in &["0.0", "-1.0", "inf", "nan"] {
+            let checker = format!(
+                "{{ type = 'checker', even = [1.0, 1.0, 1.0], odd = [0.0, 0.0, 0.0], size = {} }}",
+                size
+            );
+            assert!(
+                err(&checker).contains("checker size must be positive"),
+                "{}",
+                size
+            );
+        }
+    }
+}
@@ -1,17 +1,29 @@
 use crate::camera::Camera;
+use crate::environment::Environment;
 use crate::hittable::Hittable;
+use crate::light::LightSet;
 
 mod chapter;
+mod file;
 mod random;
 
 pub use chapter::Chapter;
+pub use file::{FileScene, LoadError};
 pub use random::Random;
 
 pub trait Scene: Send + Sync {
     type World: Hittable;
+    type Environment: Environment;
 
     fn get_camera(&self) -> &Camera;
     fn get_world(&self) -> &Self::World;
+    /// What rays that escape the world end up seeing
+    fn get_environment(&self) -> &Self::Environment;
+    /// Light-emitting objects in the world, which are sampled directly
+    fn get_lights(&self) -> &LightSet {
+        static NO_LIGHTS: LightSet = LightSet::empty();
+        &NO_LIGHTS
+    }
 
     /// Enables freecam, with specified camera position.
     fn enable_freecam(&mut self, cam: Camera);
@@ -1,8 +1,10 @@
 //! Random scene from Chapter 12 of RTIOW
-use rand::{thread_rng, Rng};
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
 
 use crate::camera::{Camera, CameraOpts};
-use crate::hittable::{HittableT, InfPlane, Sphere};
+use crate::environment::Gradient;
+use crate::hittable::{Bvh, HittableT, InfPlane, Sphere};
 use crate::material;
 use crate::vec3::Vec3;
 
@@ -11,7 +13,8 @@ use super::Scene;
 /// The Scene that was gradually expanded upon throughout RTIOW.
 pub struct Random {
     camera: Camera,
-    scene: Vec<HittableT>,
+    scene: Bvh<HittableT>,
+    sky: Gradient,
 }
 
 impl Default for Random {
@@ -24,8 +27,15 @@ impl Random {
     /// Create a new Random scene
     // TODO?: add parameter to stage the scene as it appeared at chapter X?
     pub fn new() -> Random {
-        let mut rng = thread_rng();
+        Random::from_rng(&mut thread_rng())
+    }
+
+    /// Create a new Random scene, generated using the specified `seed`
+    pub fn from_seed(seed: u64) -> Random {
+        Random::from_rng(&mut StdRng::seed_from_u64(seed))
+    }
 
+    fn from_rng(rng: &mut impl Rng) -> Random {
         let mut scene = Vec::new();
         // ground
         // scene.push(Sphere::new_hittable(
@@ -47,24 +57,20 @@ impl Random {
                 let b = b as f32;
 
                 let material = match rng.gen::<f32>() {
-                    r if r < 0.8 => {
-                        (material::Lambertian::new_material(Vec3::new(
-                            rng.gen::<f32>() * rng.gen::<f32>(),
-                            rng.gen::<f32>() * rng.gen::<f32>(),
-                            rng.gen::<f32>() * rng.gen::<f32>(),
-                        )))
-                    }
-                    r if r < 0.95 => {
-                        (material::Metal::new_material(
-                            0.5 * Vec3::new(
-                                rng.gen::<f32>() + 1.,
-                                rng.gen::<f32>() + 1.,
-                                rng.gen::<f32>() + 1.,
-                            ),
-                            0.5 * rng.gen::<f32>(),
-                        ))
-                    }
-                    _ => (material::Dielectric::new_material(1.5)),
+                    r if r < 0.8 => material::Lambertian::new_material(Vec3::new(
+                        rng.gen::<f32>() * rng.gen::<f32>(),
+                        rng.gen::<f32>() * rng.gen::<f32>(),
+                        rng.gen::<f32>() * rng.gen::<f32>(),
+                    )),
+                    r if r < 0.95 => material::Metal::new_material(
+                        0.5 * Vec3::new(
+                            rng.gen::<f32>() + 1.,
+                            rng.gen::<f32>() + 1.,
+                            rng.gen::<f32>() + 1.,
+                        ),
+                        0.5 * rng.gen::<f32>(),
+                    ),
+                    _ => material::Dielectric::new_material(1.5),
                 };
 
                 let center = Vec3::new(a + 0.9 * rng.gen::<f32>(), 0.2, b + 0.9 * rng.gen::<f32>());
@@ -102,13 +108,15 @@ impl Random {
                 aperture: 0.25,
                 focus_dist: 10.,
             }),
-            scene,
+            scene: Bvh::new(scene),
+            sky: Gradient::default(),
         }
     }
 }
 
 impl Scene for Random {
-    type World = Vec<HittableT>;
+    type World = Bvh<HittableT>;
+    type Environment = Gradient;
 
     fn get_camera(&self) -> &Camera {
         &self.camera
@@ -118,7 +126,11 @@ impl Scene for Random {
     }
     fn disable_freecam(&mut self) {}
 
-    fn get_world(&self) -> &Vec<HittableT> {
+    fn get_world(&self) -> &Bvh<HittableT> {
         &self.scene
     }
+
+    fn get_environment(&self) -> &Gradient {
+        &self.sky
+    }
 }
@@ -0,0 +1,76 @@
+use crate::vec3::Vec3;
+
+use super::{Texture, TextureT};
+
+/// A 3D checkerboard, alternating between two textures in cubes of `size`.
+///
+/// Being solid (i.e: based on the hit-point's position, rather than its
+/// surface coordinates), it can be applied to any object without distortion.
+#[derive(Debug)]
+pub struct Checker {
+    pub even: Box<TextureT>,
+    pub odd: Box<TextureT>,
+    pub size: f32,
+}
+
+impl Checker {
+    /// Return a new checker texture, alternating between textures `even` and
+    /// `odd` in cubes of `size`
+    pub fn new_texture(even: TextureT, odd: TextureT, size: f32) -> TextureT {
+        Checker {
+            even: Box::new(even),
+            odd: Box::new(odd),
+            size,
+        }
+        .into()
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, uv: (f32, f32), p: &Vec3) -> Vec3 {
+        // (whether the cell along an axis is odd, which can't overflow the way
+        // summing the cells' indices can)
+        let odd = |x: f32| ((x / self.size).floor() as i64).rem_euclid(2) == 1;
+        if !(odd(p.x) ^ odd(p.y) ^ odd(p.z)) {
+            self.even.value(uv, p)
+        } else {
+            self.odd.value(uv, p)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::Solid;
+
+    fn checker(size: f32) -> TextureT {
+        Checker::new_texture(
+            Solid::new_texture(Vec3::new(1., 1., 1.)),
+            Solid::new_texture(Vec3::new(0., 0., 0.)),
+            size,
+        )
+    }
+
+    fn is_even(t: &TextureT, x: f32, y: f32, z: f32) -> bool {
+        t.value((0., 0.), &Vec3::new(x, y, z)).x == 1.
+    }
+
+    #[test]
+    fn cells_alternate() {
+        let t = checker(0.5);
+        assert!(is_even(&t, 0.25, 0.25, 0.25));
+        assert!(!is_even(&t, 0.75, 0.25, 0.25));
+        assert!(!is_even(&t, 0.25, 0.25, -0.25));
+        assert!(is_even(&t, -0.25, -0.25, 0.25));
+        assert!(!is_even(&t, -0.25, -0.25, -0.25));
+        assert!(is_even(&t, 1.25, 0.75, -0.25));
+    }
+
+    #[test]
+    fn huge_cell_indices_dont_overflow() {
+        let t = checker(1e-30);
+        is_even(&t, 1e10, 1e10, 1e10);
+        is_even(&t, -1e10, 1e10, -1e10);
+    }
+}
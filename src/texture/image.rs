@@ -0,0 +1,284 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::vec3::Vec3;
+
+use super::{Texture, TextureT};
+
+/// How texture coordinates outside of [0, 1] are mapped onto the image
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapMode {
+    /// Tile the image
+    Repeat,
+    /// Tile the image, flipping every other copy
+    Mirror,
+    /// Extend the image's edges
+    Clamp,
+}
+
+impl WrapMode {
+    /// Map (possibly out of bounds) pixel coordinate `i` into `0..len`
+    fn apply(self, i: i64, len: usize) -> usize {
+        let len = len as i64;
+        let i = match self {
+            WrapMode::Repeat => i.rem_euclid(len),
+            WrapMode::Mirror => {
+                let i = i.rem_euclid(2 * len);
+                if i < len {
+                    i
+                } else {
+                    2 * len - 1 - i
+                }
+            }
+            WrapMode::Clamp => i.clamp(0, len - 1),
+        };
+        i as usize
+    }
+}
+
+/// A texture mapped from an image (with bilinear filtering).
+///
+/// The bottom left corner of the image lies at (u, v) = (0, 0), and the top
+/// right corner at (1, 1).
+#[derive(Debug)]
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    /// linear RGB, stored row-major, top to bottom
+    pixels: Vec<Vec3>,
+    wrap: WrapMode,
+}
+
+impl ImageTexture {
+    /// Load an image texture from a `.png` or `.ppm` file. Pixels are
+    /// expected to be sRGB encoded.
+    pub fn load(path: impl AsRef<Path>, wrap: WrapMode) -> io::Result<ImageTexture> {
+        let path = path.as_ref();
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let (width, height, pixels) = match ext.to_ascii_lowercase().as_str() {
+            "png" => read_png(path)?,
+            "ppm" => read_ppm(path)?,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "unsupported image format (expected `.png` or `.ppm`)",
+                ))
+            }
+        };
+
+        if width == 0 || height == 0 {
+            return Err(invalid_data("image is empty"));
+        }
+
+        Ok(ImageTexture {
+            width,
+            height,
+            pixels,
+            wrap,
+        })
+    }
+
+    /// Load a new image texture. See [ImageTexture::load] for details.
+    pub fn new_texture(path: impl AsRef<Path>, wrap: WrapMode) -> io::Result<TextureT> {
+        Ok(ImageTexture::load(path, wrap)?.into())
+    }
+
+    #[inline]
+    fn texel(&self, x: i64, y: i64) -> Vec3 {
+        let x = self.wrap.apply(x, self.width);
+        let y = self.wrap.apply(y, self.height);
+        self.pixels[y * self.width + x]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, (u, v): (f32, f32), _p: &Vec3) -> Vec3 {
+        // continuous pixel coords (with pixel centers at integer coords)
+        let x = u * self.width as f32 - 0.5;
+        let y = (1. - v) * self.height as f32 - 0.5;
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = (1. - fx) * self.texel(x0, y0) + fx * self.texel(x0 + 1, y0);
+        let bottom = (1. - fx) * self.texel(x0, y0 + 1) + fx * self.texel(x0 + 1, y0 + 1);
+        (1. - fy) * top + fy * bottom
+    }
+}
+
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// The inverse of the sRGB transfer function
+fn srgb_decode(x: f32) -> f32 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert 8-bit sRGB channels into a linear color
+fn srgb_to_linear(r: u8, g: u8, b: u8) -> Vec3 {
+    let c = |x: u8| srgb_decode(x as f32 / 255.);
+    Vec3::new(c(r), c(g), c(b))
+}
+
+/// Read an 8 or 16-bit PNG (of any color type), ignoring any alpha channel
+fn read_png(path: &Path) -> io::Result<(usize, usize, Vec<Vec3>)> {
+    let mut decoder = png::Decoder::new(File::open(path)?);
+    // expand palettes and low bit depths to 8-bit channels
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let (info, mut reader) = decoder.read_info()?;
+    let mut buf = vec![0; info.buffer_size()];
+    reader.next_frame(&mut buf)?;
+
+    let (width, height) = (info.width as usize, info.height as usize);
+    if width == 0 || height == 0 {
+        return Ok((width, height, Vec::new()));
+    }
+
+    let channels = info.line_size / width;
+    let pixels = buf
+        .chunks_exact(info.line_size)
+        .flat_map(|row| row[..width * channels].chunks_exact(channels))
+        .map(|px| match channels {
+            // grayscale (with or without alpha)
+            1 | 2 => srgb_to_linear(px[0], px[0], px[0]),
+            _ => srgb_to_linear(px[0], px[1], px[2]),
+        })
+        .collect();
+
+    Ok((width, height, pixels))
+}
+
+/// Read a binary (`P6`) or ASCII (`P3`) PPM, with up to 16 bits per channel
+fn read_ppm(path: &Path) -> io::Result<(usize, usize, Vec<Vec3>)> {
+    decode_ppm(&fs::read(path)?)
+}
+
+/// Decode the contents of a PPM file. See [read_ppm] for details.
+fn decode_ppm(data: &[u8]) -> io::Result<(usize, usize, Vec<Vec3>)> {
+    let mut pos = 0;
+
+    // whitespace separated tokens, with `#` comments running to the end of
+    // the line
+    let mut token = || -> io::Result<&[u8]> {
+        loop {
+            match data.get(pos) {
+                Some(c) if c.is_ascii_whitespace() => pos += 1,
+                Some(b'#') => {
+                    while data.get(pos).is_some_and(|&c| c != b'\n') {
+                        pos += 1;
+                    }
+                }
+                Some(_) => break,
+                None => return Err(invalid_data("unexpected end of PPM file")),
+            }
+        }
+        let start = pos;
+        while data.get(pos).is_some_and(|c| !c.is_ascii_whitespace()) {
+            pos += 1;
+        }
+        Ok(&data[start..pos])
+    };
+
+    let magic = token()?.to_vec();
+    let mut number = || -> io::Result<usize> {
+        std::str::from_utf8(token()?)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| invalid_data("malformed number in PPM file"))
+    };
+    let width = number()?;
+    let height = number()?;
+    let max = number()?;
+    if max == 0 || max > 65535 {
+        return Err(invalid_data(format!("unsupported PPM maxval {}", max)));
+    }
+
+    let len = width
+        .checked_mul(height)
+        .and_then(|n| n.checked_mul(3))
+        .ok_or_else(|| invalid_data("PPM image is too large"))?;
+    let samples: Vec<usize> = match magic.as_slice() {
+        b"P3" => (0..len).map(|_| number()).collect::<io::Result<_>>()?,
+        b"P6" => {
+            // exactly one whitespace character separates the header from the
+            // data
+            let start = pos + 1;
+            let bytes = if max < 256 { 1 } else { 2 };
+            let end = len
+                .checked_mul(bytes)
+                .and_then(|n| n.checked_add(start))
+                .ok_or_else(|| invalid_data("PPM image is too large"))?;
+            let raw = data
+                .get(start..end)
+                .ok_or_else(|| invalid_data("PPM file is truncated"))?;
+            match bytes {
+                1 => raw.iter().map(|&x| x as usize).collect(),
+                _ => raw
+                    .chunks_exact(2)
+                    .map(|x| u16::from_be_bytes([x[0], x[1]]) as usize)
+                    .collect(),
+            }
+        }
+        _ => return Err(invalid_data("not a PPM file (expected `P3` or `P6`)")),
+    };
+
+    let c = |x: usize| srgb_decode(x.min(max) as f32 / max as f32);
+    let pixels = samples
+        .chunks_exact(3)
+        .map(|px| Vec3::new(c(px[0]), c(px[1]), c(px[2])))
+        .collect();
+
+    Ok((width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(pixels: &[Vec3]) -> Vec<[f32; 3]> {
+        pixels.iter().map(|p| [p.x, p.y, p.z]).collect()
+    }
+
+    #[test]
+    fn ascii_and_binary_ppms_match() {
+        let ascii = b"P3\n# a comment\n2 1\n255\n0 0 0  255 255 255\n";
+        let binary = b"P6 2 1 255\n\x00\x00\x00\xff\xff\xff";
+        let (w, h, pixels) = decode_ppm(ascii).unwrap();
+        assert_eq!((w, h), (2, 1));
+        assert_eq!(rgb(&pixels), [[0., 0., 0.], [1., 1., 1.]]);
+        assert_eq!(rgb(&decode_ppm(binary).unwrap().2), rgb(&pixels));
+    }
+
+    #[test]
+    fn malformed_ppms_are_rejected() {
+        let huge = format!("P6 {} {} 255\n", usize::MAX / 2, 3);
+        for data in [
+            huge.as_bytes(),
+            b"P6 4 4 255\n\x00\x00",
+            b"P3 1 1 255\n0 0",
+            b"P3 1 1 0\n0 0 0",
+            b"P5 1 1 255\n\x00",
+        ] {
+            let err = decode_ppm(data).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn wrap_modes() {
+        let wrap = |mode: WrapMode| (-3..5).map(|i| mode.apply(i, 3)).collect::<Vec<_>>();
+        assert_eq!(wrap(WrapMode::Repeat), [0, 1, 2, 0, 1, 2, 0, 1]);
+        assert_eq!(wrap(WrapMode::Mirror), [2, 1, 0, 0, 1, 2, 2, 1]);
+        assert_eq!(wrap(WrapMode::Clamp), [0, 0, 0, 0, 1, 2, 2, 2]);
+    }
+}
@@ -0,0 +1,123 @@
+//! Textures, which vary a material's color across its surface (or through
+//! space).
+
+use crate::vec3::Vec3;
+
+mod checker;
+mod image;
+mod noise;
+mod solid;
+
+pub use self::image::{ImageTexture, WrapMode};
+pub use checker::Checker;
+pub use noise::{Noise, NoiseKind};
+pub use solid::Solid;
+
+/// A color that varies across a surface
+pub trait Texture: Send + Sync + std::fmt::Debug {
+    /// Returns the texture's color at surface coordinates `uv`, and hit-point
+    /// `p`.
+    fn value(&self, uv: (f32, f32), p: &Vec3) -> Vec3;
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "enum_dispatch")] {
+        pub type TextureT = Textures;
+    } else {
+        pub type TextureT = Box<dyn Texture>;
+    }
+}
+
+/// Plain colors can be used wherever a texture is expected
+impl From<Vec3> for TextureT {
+    fn from(color: Vec3) -> TextureT {
+        Solid { color }.into()
+    }
+}
+
+macro_rules! impl_ref {
+    ($type:ty) => {
+        impl Texture for $type {
+            fn value(&self, uv: (f32, f32), p: &Vec3) -> Vec3 {
+                (**self).value(uv, p)
+            }
+        }
+    };
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "enum_dispatch")] {
+        // When using enum dispatch:
+        // - create the enum
+        // - Implements Texture for &enum and &mut enum
+        // - Implements From<variant> for enum
+        // - Implements enum dispatch by implementing Texture on the enum
+        macro_rules! textures {
+            (
+                $(#[$meta:meta])*
+                $(pub)? enum $enum_name:ident {
+                    $($tex_name:ident($tex_type:ty),)*
+                }
+            ) => {
+                $(#[$meta])*
+                pub enum $enum_name {
+                    $($tex_name($tex_type),)*
+                }
+
+                impl_ref!(&$enum_name);
+                impl_ref!(&mut $enum_name);
+
+                $(
+                    impl From<$tex_type> for $enum_name {
+                        fn from(x: $tex_type) -> $enum_name {
+                            $enum_name::$tex_name(x)
+                        }
+                    }
+                )*
+
+                impl Texture for $enum_name {
+                    fn value(&self, uv: (f32, f32), p: &Vec3) -> Vec3 {
+                        use self::$enum_name::*;
+                        match self {
+                            $($tex_name(x) => x.value(uv, p),)*
+                        }
+                    }
+                }
+            };
+        }
+    } else {
+        // When using dynamic dispatch:
+        // - Implement Texture for various Box<dyn>, &dyn, and &mut dyn Texture
+        // - Implement From<Texture> for Box<dyn Texture> for each Texture type
+        macro_rules! textures {
+            (
+                $(#[$meta:meta])*
+                $(pub)? enum $enum_name:ident {
+                    $($tex_name:ident($tex_type:ty),)*
+                }
+            ) => {
+                impl_ref!(Box<dyn Texture>);
+                impl_ref!(&dyn Texture);
+                impl_ref!(&mut dyn Texture);
+
+                $(
+                    impl From<$tex_type> for Box<dyn Texture> {
+                        fn from(x: $tex_type) -> Box<dyn Texture> {
+                            Box::new(x)
+                        }
+                    }
+                )*
+            };
+        }
+    }
+}
+
+textures! {
+    #[derive(Debug)]
+    pub enum Textures {
+        Checker(Checker),
+        ImageTexture(ImageTexture),
+        Noise(Noise),
+        Solid(Solid),
+    }
+}
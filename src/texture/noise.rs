@@ -0,0 +1,158 @@
+use serde::Deserialize;
+
+use crate::util::hash;
+use crate::vec3::Vec3;
+
+use super::{Texture, TextureT};
+
+/// Number of octaves summed by [Perlin::turbulence]
+const TURBULENCE_OCTAVES: usize = 7;
+
+/// The pattern made by a [Noise] texture
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseKind {
+    /// Smooth, blobby noise
+    Perlin,
+    /// Several octaves of noise, giving a cloudy, fractal look
+    Turbulence,
+    /// Stripes along z, distorted by turbulence
+    Marble,
+}
+
+/// A procedural (solid) texture, based on Perlin noise
+#[derive(Debug)]
+pub struct Noise {
+    perlin: Perlin,
+    kind: NoiseKind,
+    /// frequency of the noise
+    scale: f32,
+    color: Vec3,
+}
+
+impl Noise {
+    /// Return a new noise texture, modulating `color` by a pattern of the given
+    /// `kind`. The pattern's frequency is set by `scale`, and its randomness
+    /// by `seed`.
+    pub fn new_texture(kind: NoiseKind, scale: f32, color: Vec3, seed: u64) -> TextureT {
+        Noise {
+            perlin: Perlin::new(seed),
+            kind,
+            scale,
+            color,
+        }
+        .into()
+    }
+}
+
+impl Texture for Noise {
+    fn value(&self, _uv: (f32, f32), p: &Vec3) -> Vec3 {
+        let p = self.scale * *p;
+        let t = match self.kind {
+            NoiseKind::Perlin => 0.5 * (1. + self.perlin.noise(&p)),
+            NoiseKind::Turbulence => self.perlin.turbulence(&p),
+            NoiseKind::Marble => 0.5 * (1. + (p.z + 10. * self.perlin.turbulence(&p)).sin()),
+        };
+        self.color * t
+    }
+}
+
+/// Gradient noise.
+///
+/// See "Improving Noise" (Perlin 2002)
+#[derive(Debug)]
+struct Perlin {
+    /// a random permutation of 0..256, repeated twice (to avoid wrapping
+    /// indices)
+    perm: Vec<u8>,
+}
+
+impl Perlin {
+    fn new(seed: u64) -> Perlin {
+        let mut p: Vec<u8> = (0..=255).collect();
+        // Fisher-Yates shuffle
+        for i in (1..p.len()).rev() {
+            let j = hash(&[seed, i as u64]) % (i as u64 + 1);
+            p.swap(i, j as usize);
+        }
+
+        let perm = p.iter().chain(&p).copied().collect();
+        Perlin { perm }
+    }
+
+    /// Returns the noise at point `p`, in [-1, 1]
+    fn noise(&self, p: &Vec3) -> f32 {
+        let (fx, fy, fz) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let cell = |f: f32| (f as i64 & 255) as usize;
+        let (xi, yi, zi) = (cell(fx), cell(fy), cell(fz));
+        let (x, y, z) = (p.x - fx, p.y - fy, p.z - fz);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        // hash the coordinates of the cell's 8 corners
+        let perm = |i: usize| self.perm[i] as usize;
+        let a = perm(xi) + yi;
+        let (aa, ab) = (perm(a) + zi, perm(a + 1) + zi);
+        let b = perm(xi + 1) + yi;
+        let (ba, bb) = (perm(b) + zi, perm(b + 1) + zi);
+
+        let g = |i: usize, x: f32, y: f32, z: f32| grad(self.perm[i], x, y, z);
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, g(aa, x, y, z), g(ba, x - 1., y, z)),
+                lerp(u, g(ab, x, y - 1., z), g(bb, x - 1., y - 1., z)),
+            ),
+            lerp(
+                v,
+                lerp(u, g(aa + 1, x, y, z - 1.), g(ba + 1, x - 1., y, z - 1.)),
+                lerp(
+                    u,
+                    g(ab + 1, x, y - 1., z - 1.),
+                    g(bb + 1, x - 1., y - 1., z - 1.),
+                ),
+            ),
+        )
+    }
+
+    /// Returns the sum of several octaves of noise at point `p` (each octave
+    /// having double the frequency, and half the amplitude of the last)
+    fn turbulence(&self, p: &Vec3) -> f32 {
+        let mut sum = 0.;
+        let mut p = *p;
+        let mut weight = 1.;
+        for _ in 0..TURBULENCE_OCTAVES {
+            sum += weight * self.noise(&p).abs();
+            weight *= 0.5;
+            p *= 2.;
+        }
+        sum
+    }
+}
+
+/// Smoothstep-like curve, with zero first and second derivatives at 0 and 1
+#[inline]
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+#[inline]
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    a + t * (b - a)
+}
+
+/// Dot product of (x, y, z) with one of 12 gradient directions, picked by
+/// `hash`
+#[inline]
+fn grad(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
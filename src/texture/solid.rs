@@ -0,0 +1,22 @@
+use crate::vec3::Vec3;
+
+use super::{Texture, TextureT};
+
+/// The same color everywhere
+#[derive(Debug)]
+pub struct Solid {
+    pub color: Vec3,
+}
+
+impl Solid {
+    /// Return a new texture of a single `color`
+    pub fn new_texture(color: Vec3) -> TextureT {
+        Solid { color }.into()
+    }
+}
+
+impl Texture for Solid {
+    fn value(&self, _uv: (f32, f32), _p: &Vec3) -> Vec3 {
+        self.color
+    }
+}
@@ -1,29 +1,105 @@
 //! Misc. utility functions that are used throughout the codebase
 
-use rand::Rng;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
 
 use crate::vec3::Vec3;
 
-/// Return a random point within the unit sphere
-pub fn rand_in_unit_sphere() -> Vec3 {
-    let mut rng = rand::thread_rng();
-    loop {
-        let p = 2.0 * Vec3::new(rng.gen(), rng.gen(), rng.gen()) - Vec3::new(1., 1., 1.);
-        if p.squared_length() < 1.0 {
-            break p;
-        }
-    }
+/// The RNG used while rendering. Unlike `thread_rng`, it's cheap to create,
+/// and produces the same sequence of numbers on every platform.
+pub type RenderRng = rand_pcg::Pcg32;
+
+/// Return the RNG used to render sample `sample` of pixel (`x`, `y`).
+///
+/// Every sample gets its own independent stream of numbers, so a render
+/// depends only on `seed` (and not on how its pixels are divided between
+/// threads, or the order they're rendered in).
+pub fn sample_rng(seed: u64, x: usize, y: usize, sample: usize) -> RenderRng {
+    let h = hash(&[seed, x as u64, y as u64, sample as u64]);
+    // (using PCG's default stream)
+    RenderRng::new(h, 0x0a02_bdbf_7bb3_c0a7)
 }
 
-/// Return a random point within the unit sphere
-pub fn rand_in_unit_circle() -> Vec3 {
-    let mut rng = rand::thread_rng();
-    loop {
-        let p = 2.0 * Vec3::new(rng.gen(), rng.gen(), 0.) - Vec3::new(1., 1., 0.);
-        if p.squared_length() < 1.0 {
-            break p;
-        }
+/// Hash a sequence of integers into a single (well-mixed) integer
+pub fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0, |h, &v| splitmix64(h ^ v))
+}
+
+/// SplitMix64's finalizer, which thoroughly mixes the bits of `x`
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Map a point in the unit square to a point in the unit disk (using
+/// Shirley and Chiu's concentric mapping, which preserves stratification)
+pub fn concentric_disk((u, v): (f32, f32)) -> (f32, f32) {
+    let (a, b) = (2. * u - 1., 2. * v - 1.);
+    if a == 0. && b == 0. {
+        return (0., 0.);
     }
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, FRAC_PI_4 * (b / a))
+    } else {
+        (b, FRAC_PI_2 - FRAC_PI_4 * (a / b))
+    };
+    (r * theta.cos(), r * theta.sin())
+}
+
+/// Returns two unit vectors which (along with `n`) form an orthonormal basis
+///
+/// See "Building an Orthonormal Basis, Revisited" (Duff et al. 2017)
+pub fn orthonormal_basis(n: &Vec3) -> (Vec3, Vec3) {
+    let sign = 1f32.copysign(n.z);
+    let a = -1. / (sign + n.z);
+    let b = n.x * n.y * a;
+    (
+        Vec3::new(1. + sign * n.x * n.x * a, sign * b, -sign * n.x),
+        Vec3::new(b, sign + n.y * n.y * a, -n.y),
+    )
+}
+
+/// Map a point in the unit square to a (unit) direction in the hemisphere
+/// around (unit) normal `n`, with a cosine-weighted distribution
+pub fn cosine_hemisphere(n: &Vec3, u: (f32, f32)) -> Vec3 {
+    let (x, y) = concentric_disk(u);
+    let z = (1. - x * x - y * y).max(0.).sqrt();
+    let (t, b) = orthonormal_basis(n);
+    x * t + y * b + z * *n
+}
+
+/// Relative luminance of linear color `c`
+#[inline]
+pub fn luminance(c: Vec3) -> f32 {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
+
+/// Map a point in the unit square to a point on the unit sphere (with a
+/// uniform distribution)
+pub fn uniform_sphere((u, v): (f32, f32)) -> Vec3 {
+    let z = 1. - 2. * u;
+    let r = (1. - z * z).max(0.).sqrt();
+    let phi = 2. * PI * v;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Map a point in the unit square to a (unit) direction within the cone around
+/// (unit) `axis` whose half-angle has a cosine of `1 - width`, with a uniform
+/// distribution (i.e: a pdf of `1 / (2 * PI * width)`)
+pub fn uniform_cone(axis: &Vec3, width: f32, (u, v): (f32, f32)) -> Vec3 {
+    let one_minus_cos = u * width;
+    let cos_theta = 1. - one_minus_cos;
+    let sin_theta = (one_minus_cos * (2. - one_minus_cos)).max(0.).sqrt();
+    let phi = 2. * PI * v;
+    let (t, b) = orthonormal_basis(axis);
+    sin_theta * phi.cos() * t + sin_theta * phi.sin() * b + cos_theta * *axis
+}
+
+/// Map a point in the unit cube to a point in the unit ball (with a uniform
+/// distribution)
+pub fn uniform_ball(u: (f32, f32), w: f32) -> Vec3 {
+    w.cbrt() * uniform_sphere(u)
 }
 
 const AVG_SIZE: usize = 1;
@@ -42,6 +118,7 @@ impl SmoothAvg {
         }
     }
 
+    #[allow(clippy::modulo_one)] // AVG_SIZE is meant to be tweaked
     pub fn update(&mut self, v: f32) {
         if !v.is_normal() {
             // INF, -INF, or zero
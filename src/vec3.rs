@@ -1,8 +1,11 @@
-use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::ops::{Add, Div, Index, Mul, Neg, Sub};
 use std::ops::{AddAssign, DivAssign, MulAssign, SubAssign};
 
+use serde::Deserialize;
+
 /// General purpose 3D Vector class
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(from = "[f32; 3]")]
 pub struct Vec3 {
     pub x: f32,
     pub y: f32,
@@ -59,6 +62,39 @@ impl Vec3 {
     pub fn reflect_through(&self, n: &Vec3) -> Vec3 {
         *self - 2. * self.dot(n) * n
     }
+
+    /// Component-wise minimum
+    #[inline]
+    pub fn min(&self, v2: &Vec3) -> Vec3 {
+        Vec3::new(self.x.min(v2.x), self.y.min(v2.y), self.z.min(v2.z))
+    }
+
+    /// Component-wise maximum
+    #[inline]
+    pub fn max(&self, v2: &Vec3) -> Vec3 {
+        Vec3::new(self.x.max(v2.x), self.y.max(v2.y), self.z.max(v2.z))
+    }
+}
+
+/// Access components by axis index (0 = x, 1 = y, 2 = z)
+impl Index<usize> for Vec3 {
+    type Output = f32;
+
+    #[inline]
+    fn index(&self, axis: usize) -> &f32 {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 axis out of bounds: {}", axis),
+        }
+    }
+}
+
+impl From<[f32; 3]> for Vec3 {
+    fn from([x, y, z]: [f32; 3]) -> Vec3 {
+        Vec3 { x, y, z }
+    }
 }
 
 macro_rules! impl_Op {